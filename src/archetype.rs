@@ -1,133 +1,530 @@
 // src/archetype.rs
 
-use crate::config::Config;
+use crate::config::{Config, TemplateLocation};
+use crate::embedded;
+use crate::git_templates;
+use crate::hooks::{self, Hooks, Phase};
+use crate::lock::{self, ArchetypeLock};
+use crate::render;
 use anyhow::{anyhow, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
 use handlebars::Handlebars;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use indexmap::IndexMap;
+use regex::Regex;
 use serde::Deserialize;
 use serde_json::json;
-use shlex::Shlex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 #[derive(Deserialize, Debug)]
-struct ArchetypeConfig {
-    #[allow(dead_code)]
-    description: String,
+pub(crate) struct ArchetypeConfig {
+    pub(crate) description: String,
+    /// 父原型名稱；子原型的檔案、變數與鉤子會疊加在父原型之上
+    pub(crate) extends: Option<String>,
+    /// 依宣告順序提示；後面的預設值可以引用前面已回答的變數
     #[serde(default)]
-    variables: HashMap<String, ArchetypeVariable>,
+    pub(crate) variables: IndexMap<String, ArchetypeVariable>,
     #[serde(default)]
-    hooks: Hooks,
+    pub(crate) hooks: Hooks,
+    /// glob -> Handlebars 條件；條件為 false 時不產生符合的檔案或目錄
+    #[serde(default)]
+    pub(crate) files: HashMap<String, String>,
+    /// 永遠不產生的檔案或目錄；語法同 .gitignore
+    #[serde(default)]
+    pub(crate) exclude: Vec<String>,
+    /// 原樣複製、不當作模板渲染的檔案，語法同 .gitignore；路徑仍會渲染
+    #[serde(default)]
+    pub(crate) raw: Vec<String>,
 }
 
-#[derive(Deserialize, Debug)]
-struct ArchetypeVariable {
-    prompt: String,
-    default: Option<String>,
+impl ArchetypeConfig {
+    /// 讀取並驗證模板目錄中的 archetype.toml
+    pub(crate) fn read(template_path: &Path) -> Result<Self> {
+        let archetype_config_path = template_path.join("archetype.toml");
+        let content =
+            fs::read_to_string(&archetype_config_path).context("Failed to read archetype.toml")?;
+        let config: ArchetypeConfig =
+            toml::from_str(&content).context("Failed to parse archetype.toml")?;
+        for (key, var_info) in &config.variables {
+            var_info.validate(key)?;
+        }
+        Archetype::compile_file_rules(&config.files)?;
+        compile_globs(&config.exclude, "exclude")?;
+        compile_globs(&config.raw, "raw")?;
+        Ok(config)
+    }
+
+    /// 將子原型疊加在父原型之上：同名變數與檔案規則以子原型為準，鉤子與 exclude/raw 依序串接
+    fn merge_onto(self, parent: ArchetypeConfig) -> ArchetypeConfig {
+        let mut variables = parent.variables;
+        variables.extend(self.variables);
+        let mut files = parent.files;
+        files.extend(self.files);
+        let mut exclude = parent.exclude;
+        exclude.extend(self.exclude);
+        let mut raw = parent.raw;
+        raw.extend(self.raw);
+        ArchetypeConfig {
+            description: self.description,
+            extends: self.extends,
+            variables,
+            hooks: self.hooks.merge_onto(parent.hooks),
+            files,
+            exclude,
+            raw,
+        }
+    }
 }
 
-#[derive(Deserialize, Debug, Default)]
-struct Hooks {
+#[derive(Deserialize, Debug)]
+pub(crate) struct ArchetypeVariable {
+    pub(crate) prompt: String,
+    #[serde(rename = "type", default)]
+    pub(crate) var_type: VariableType,
+    pub(crate) default: Option<toml::Value>,
     #[serde(default)]
-    post_create: PostCreateHooks,
+    pub(crate) choices: Vec<String>,
+    pub(crate) pattern: Option<String>,
+    /// 提示下方顯示的說明文字
+    pub(crate) help: Option<String>,
+    /// 提示分組的標題；連續同組的變數只顯示一次
+    pub(crate) group: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Default)]
-struct PostCreateHooks {
-    #[serde(default)]
-    commands: Vec<String>,
+/// 變數型別；決定輸入如何驗證以及在模板中呈現為何種 JSON 值
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum VariableType {
+    #[default]
+    String,
+    Bool,
+    Choice,
+    Int,
+}
+
+impl ArchetypeVariable {
+    /// 檢查變數定義本身是否合理（choices、pattern 與 default）
+    fn validate(&self, key: &str) -> Result<()> {
+        if self.var_type == VariableType::Choice && self.choices.is_empty() {
+            return Err(anyhow!(
+                "Variable '{}' is of type 'choice' but declares no choices",
+                key
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            Regex::new(pattern)
+                .with_context(|| format!("Invalid pattern for variable '{}'", key))?;
+        }
+        for candidate in self.default_candidates() {
+            if !candidate.as_str().is_some_and(is_computed) {
+                self.parse_answer(&value_text(candidate))
+                    .with_context(|| format!("Invalid default for variable '{}'", key))?;
+            }
+        }
+        for template in self.default_templates() {
+            handlebars::Template::compile(template)
+                .with_context(|| format!("Invalid default template for variable '{}'", key))?;
+        }
+        Ok(())
+    }
+
+    /// 預設值的文字形式（TOML 中可寫成字串、布林、整數，或依序嘗試的候選陣列）
+    pub(crate) fn default_text(&self) -> Option<String> {
+        self.default.as_ref().map(|v| match v {
+            toml::Value::Array(items) => {
+                items.iter().map(value_text).collect::<Vec<_>>().join(" | ")
+            }
+            other => value_text(other),
+        })
+    }
+
+    fn default_candidates(&self) -> Vec<&toml::Value> {
+        match &self.default {
+            Some(toml::Value::Array(items)) => items.iter().collect(),
+            Some(value) => vec![value],
+            None => Vec::new(),
+        }
+    }
+
+    /// 需要以 Handlebars 渲染的預設值候選
+    pub(crate) fn default_templates(&self) -> Vec<&str> {
+        self.default_candidates()
+            .into_iter()
+            .filter_map(|v| v.as_str())
+            .filter(|s| is_computed(s) && !is_lookup(s))
+            .collect()
+    }
+
+    /// 依序解析預設值的候選，回傳第一個非空且合法的結果：
+    /// `env:NAME` 讀取環境變數、`git:key` 讀取 `git config`，
+    /// 其他字串以已回答的變數當作 Handlebars 模板渲染。
+    pub(crate) fn resolve_default(
+        &self,
+        context: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Option<String>> {
+        for candidate in self.default_candidates() {
+            let text = match candidate {
+                toml::Value::String(s) => {
+                    if let Some(name) = s.strip_prefix("env:") {
+                        std::env::var(name).unwrap_or_default()
+                    } else if let Some(key) = s.strip_prefix("git:") {
+                        git_config(key).unwrap_or_default()
+                    } else {
+                        render::handlebars()
+                            .render_template(s, context)
+                            .with_context(|| format!("Failed to render default '{}'", s))?
+                    }
+                }
+                other => value_text(other),
+            };
+            if !text.is_empty() && self.parse_answer(&text).is_ok() {
+                return Ok(Some(text));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 提示中顯示的可接受值
+    fn hint(&self) -> String {
+        match self.var_type {
+            VariableType::String => String::new(),
+            VariableType::Bool => " [y/n]".to_string(),
+            VariableType::Int => " [integer]".to_string(),
+            VariableType::Choice => format!(" [{}]", self.choices.join("/")),
+        }
+    }
+
+    /// 驗證輸入並轉換成對應型別的 JSON 值
+    fn parse_answer(&self, raw: &str) -> Result<serde_json::Value> {
+        if let Some(pattern) = &self.pattern {
+            let re = Regex::new(&format!("^(?:{})$", pattern))?;
+            if !re.is_match(raw) {
+                return Err(anyhow!("'{}' does not match pattern '{}'", raw, pattern));
+            }
+        }
+        match self.var_type {
+            VariableType::String => Ok(json!(raw)),
+            VariableType::Bool => match raw.to_ascii_lowercase().as_str() {
+                "y" | "yes" | "true" | "on" | "1" => Ok(json!(true)),
+                "n" | "no" | "false" | "off" | "0" => Ok(json!(false)),
+                _ => Err(anyhow!("'{}' is not a boolean (expected y/n)", raw)),
+            },
+            VariableType::Int => raw
+                .parse::<i64>()
+                .map(|n| json!(n))
+                .map_err(|_| anyhow!("'{}' is not an integer", raw)),
+            VariableType::Choice => {
+                if self.choices.iter().any(|c| c == raw) {
+                    Ok(json!(raw))
+                } else {
+                    Err(anyhow!(
+                        "'{}' is not one of: {}",
+                        raw,
+                        self.choices.join(", ")
+                    ))
+                }
+            }
+        }
+    }
+
+    /// 未提供輸入時使用的值：優先採用 default，否則為型別的零值
+    fn default_value(
+        &self,
+        context: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        match self.resolve_default(context)? {
+            Some(default) => self.parse_answer(&default),
+            None => Ok(match self.var_type {
+                VariableType::String => json!(""),
+                VariableType::Bool => json!(false),
+                VariableType::Int => json!(0),
+                VariableType::Choice => json!(self.choices.first()),
+            }),
+        }
+    }
 }
 
 /// 代表一個已載入記憶體的專案原型
 pub struct Archetype {
     pub name: String,
-    config: ArchetypeConfig,
-    template_path: PathBuf,
+    pub(crate) config: ArchetypeConfig,
+    pub(crate) template_path: PathBuf,
+    /// 繼承鏈上的模板目錄，由最上層的父原型排到自己
+    pub(crate) layers: Vec<PathBuf>,
 }
 
 impl Archetype {
     /// 從組態和原型名稱載入
     pub fn load(app_config: &Config, name: &str) -> Result<Self> {
-        // Build candidate relative paths for the archetype
-        let mut candidates: Vec<String> = Vec::new();
-        if let Some(mapped) = app_config.archetypes.get(name) {
-            candidates.push(mapped.clone());
-        }
-        // Built-in aliases for convenience when config is missing
-        match name {
-            "app" | "exe" | "executable" => candidates.push("default/executable".to_string()),
-            "lib" | "library" => candidates.push("default/library".to_string()),
-            _ => {}
-        }
-        // Also allow direct folder name usage
-        candidates.push(name.to_string());
-
         // 在所有模板位置中尋找原型
-        let mut template_locations = app_config.templates.locations.clone();
-        // 將內建模板位置加入搜尋路徑
-        // 1) 相對於目前工作目錄
-        template_locations.push(PathBuf::from("./templates"));
-        // 2) 相對於專案來源根目錄（無論從哪個 CWD 執行，都可找到）
-        template_locations.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("templates"));
-
-        let mut final_template_path: Option<PathBuf> = None;
-
-        'outer: for loc in &template_locations {
-            for rel in &candidates {
-                let potential_path = loc.join(rel);
-                if potential_path.exists() {
-                    final_template_path = Some(potential_path);
-                    break 'outer;
-                }
-            }
-        }
+        let roots = template_roots(app_config)?;
 
-        let template_path = final_template_path
+        let template_path = resolve_template_path(app_config, &roots, name)
             .ok_or_else(|| anyhow!("Could not find template directory for archetype '{}'", name))?;
+        Self::load_path(app_config, &roots, name, template_path)
+    }
 
-        let archetype_config_path = template_path.join("archetype.toml");
-        let content =
-            fs::read_to_string(&archetype_config_path).context("Failed to read archetype.toml")?;
-        let config: ArchetypeConfig =
-            toml::from_str(&content).context("Failed to parse archetype.toml")?;
+    /// 從指定的模板目錄載入，父原型仍在模板位置中解析
+    pub(crate) fn load_path(
+        app_config: &Config,
+        roots: &[TemplateRoot],
+        name: &str,
+        template_path: PathBuf,
+    ) -> Result<Self> {
+        let mut config = ArchetypeConfig::read(&template_path)?;
+        let mut layers = vec![template_path.clone()];
+
+        // 沿著 extends 往上解析父原型
+        let mut parent_name = config.extends.clone();
+        while let Some(parent) = parent_name {
+            let parent_path = resolve_template_path(app_config, roots, &parent)
+                .ok_or_else(|| anyhow!("Could not find parent archetype '{}'", parent))?;
+            if layers.contains(&parent_path) {
+                return Err(anyhow!(
+                    "Archetype inheritance cycle detected at '{}'",
+                    parent
+                ));
+            }
+            let parent_config = ArchetypeConfig::read(&parent_path)
+                .with_context(|| format!("Failed to load parent archetype '{}'", parent))?;
+            parent_name = parent_config.extends.clone();
+            config = config.merge_onto(parent_config);
+            layers.insert(0, parent_path);
+        }
 
         Ok(Archetype {
             name: name.to_string(),
             config,
             template_path,
+            layers,
         })
     }
 
+    /// 收集所有要產生的模板檔案（相對路徑 -> 來源路徑）；子原型的檔案覆蓋父原型的同名檔案。
+    /// 符合 `exclude` 或該層 `.cprojectignore` 的檔案不會列入。
+    pub(crate) fn template_files(&self) -> Result<BTreeMap<PathBuf, PathBuf>> {
+        Ok(self.walk_layers()?.0)
+    }
+
+    /// 模板中的空目錄（相對路徑）；其他目錄會隨著其中的檔案一起建立
+    fn empty_template_dirs(&self) -> Result<Vec<PathBuf>> {
+        let (files, dirs) = self.walk_layers()?;
+        Ok(dirs
+            .iter()
+            .filter(|dir| {
+                !files.keys().any(|f| f.starts_with(dir))
+                    && !dirs.iter().any(|d| d != *dir && d.starts_with(dir))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// 要在新專案中建立的空目錄（已渲染的相對路徑）。
+    /// 以目錄中的路徑判斷 `[files]` 條件，`tests/**` 這類規則才會排除整個目錄
+    fn rendered_empty_dirs(&self, context: &serde_json::Value) -> Result<Vec<PathBuf>> {
+        let hbs = render::handlebars();
+        let rules = Self::compile_file_rules(&self.config.files)?;
+        let mut rendered = Vec::new();
+        for dir in self.empty_template_dirs()? {
+            if !Self::is_included(&hbs, &rules, &dir.join("x"), context)? {
+                continue;
+            }
+            let path = hbs
+                .render_template(&dir.to_string_lossy(), context)
+                .with_context(|| format!("Failed to render path '{}'", dir.display()))?;
+            rendered.push(PathBuf::from(path));
+        }
+        Ok(rendered)
+    }
+
+    /// 疊加所有層的檔案與目錄，略過 exclude、.cprojectignore 與 generators/
+    fn walk_layers(&self) -> Result<(BTreeMap<PathBuf, PathBuf>, BTreeSet<PathBuf>)> {
+        let exclude = compile_globs(&self.config.exclude, "exclude")?;
+        let mut files = BTreeMap::new();
+        let mut dirs = BTreeSet::new();
+        for layer in &self.layers {
+            let ignore = layer_ignore(layer)?;
+            let walker = walkdir::WalkDir::new(layer).min_depth(1).into_iter();
+            for entry in walker.filter_map(Result::ok) {
+                let file_name = entry.path().file_name().unwrap_or_default();
+                let is_dir = entry.file_type().is_dir();
+                if !is_dir && (file_name == "archetype.toml" || file_name == IGNORE_FILE) {
+                    continue;
+                }
+                let rel_path = entry.path().strip_prefix(layer)?.to_path_buf();
+                if rel_path.starts_with(GENERATORS_DIR)
+                    || exclude
+                        .matched_path_or_any_parents(&rel_path, is_dir)
+                        .is_ignore()
+                    || ignore
+                        .matched_path_or_any_parents(&rel_path, is_dir)
+                        .is_ignore()
+                {
+                    continue;
+                }
+                if is_dir {
+                    dirs.insert(rel_path);
+                } else {
+                    files.insert(rel_path, entry.path().to_path_buf());
+                }
+            }
+        }
+        Ok((files, dirs))
+    }
+
+    /// 原型提供的產生器種類（`generators/` 下的子目錄名稱）
+    pub(crate) fn generator_kinds(&self) -> Vec<String> {
+        let mut kinds: Vec<String> = self
+            .layers
+            .iter()
+            .filter_map(|layer| fs::read_dir(layer.join(GENERATORS_DIR)).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    /// 某個產生器的模板檔案（相對路徑 -> 來源路徑）；子原型的檔案覆蓋父原型的同名檔案
+    pub(crate) fn generator_files(&self, kind: &str) -> Result<BTreeMap<PathBuf, PathBuf>> {
+        let mut files = BTreeMap::new();
+        for layer in &self.layers {
+            let root = layer.join(GENERATORS_DIR).join(kind);
+            for entry in walkdir::WalkDir::new(&root)
+                .into_iter()
+                .filter_map(Result::ok)
+            {
+                if entry.file_type().is_file() {
+                    let rel_path = entry.path().strip_prefix(&root)?.to_path_buf();
+                    files.insert(rel_path, entry.path().to_path_buf());
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// 模板中的相對路徑是否符合 `raw`，應原樣複製
+    pub(crate) fn is_raw(&self, rel_path: &Path) -> Result<bool> {
+        let raw = compile_globs(&self.config.raw, "raw")?;
+        Ok(raw.matched_path_or_any_parents(rel_path, false).is_ignore())
+    }
+
     /// 實例化原型，生成專案
     pub fn instantiate(
         &self,
         project_name: &str,
         destination: &Path,
-        use_defaults: bool,
+        options: &InstantiateOptions,
     ) -> Result<()> {
-        if destination.exists() {
-            return Err(anyhow!(
-                "Destination '{}' already exists",
-                destination.display()
-            ));
-        }
-        fs::create_dir_all(destination)?;
-
+        // `cproject init` 寫入既有目錄；改用絕對路徑，鉤子與訊息中的路徑才會一致
+        let canonical;
+        let destination = match options.existing {
+            Some(_) => {
+                canonical = fs::canonicalize(destination).with_context(|| {
+                    format!("Destination '{}' does not exist", destination.display())
+                })?;
+                canonical.as_path()
+            }
+            None if destination.exists() => {
+                return Err(anyhow!(
+                    "Destination '{}' already exists",
+                    destination.display()
+                ));
+            }
+            None => destination,
+        };
         // 1. 收集變數
+        self.check_provided_vars(&options.vars)?;
+        // 沒有終端機可以回答提示時（例如在腳本中執行），改用預設值而不是卡在讀取輸入
+        let use_defaults = options.use_defaults || !std::io::stdin().is_terminal();
+        if use_defaults && !options.use_defaults && self.has_prompts(&options.vars) {
+            println!("ℹ️ stdin is not a terminal; using default values (pass --defaults to silence this)");
+        }
         let context_data = if use_defaults {
-            self.collect_variables_with_defaults(project_name, destination)?
+            self.collect_variables_with_defaults(project_name, destination, &options.vars)?
         } else {
-            self.collect_variables_interactively(project_name, destination)?
+            self.collect_variables_interactively(project_name, destination, &options.vars)?
         };
 
-        // 2. 渲染模板
-        println!("🚀 Rendering template for '{}'...", self.name);
-        self.render_template_dir(destination, &context_data)?;
+        if options.dry_run {
+            return self.print_dry_run(destination, &context_data, options.existing);
+        }
+
+        // 2. 渲染模板：先寫入暫存目錄，成功後再搬到目的地
+        //    pre_render / post_render 鉤子在暫存目錄中執行
+        let inside_git = Self::is_inside_git_repo(destination)?;
+        let staging = staging_dir(destination, options.existing.is_some());
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        let staged = hooks::run_phase(
+            Phase::PreRender,
+            &self.config.hooks.pre_render.0,
+            &staging,
+            &context_data,
+            inside_git,
+        )
+        .and_then(|_| {
+            println!("🚀 Rendering template for '{}'...", self.name);
+            let written = self.render_template_dir(&staging, &context_data, inside_git)?;
+            self.write_lock(&staging, &context_data, &written, inside_git)
+        })
+        .and_then(|_| {
+            hooks::run_phase(
+                Phase::PostRender,
+                &self.config.hooks.post_render.0,
+                &staging,
+                &context_data,
+                inside_git,
+            )
+        });
+        if let Err(e) = staged {
+            return Err(rollback_staging(e, &staging, options.existing.is_some()));
+        }
+        if let Some(policy) = options.existing {
+            let moved = self
+                .rendered_empty_dirs(&context_data)
+                .and_then(|dirs| move_into(&staging, destination, policy, &dirs));
+            return match moved {
+                Ok(()) => {
+                    fs::remove_dir_all(&staging)?;
+                    self.finish_init(project_name, destination, &context_data, inside_git)
+                }
+                Err(e) => Err(rollback_staging(e, &staging, true)),
+            };
+        }
+        if let Err(e) = fs::rename(&staging, destination) {
+            let e = anyhow::Error::new(e).context(format!(
+                "Failed to move '{}' into place",
+                destination.display()
+            ));
+            return Err(rollback(e, &staging, "staging directory"));
+        }
 
         // 3. 執行鉤子
-        println!("🎣 Running post-create hooks...");
-        self.run_hooks(destination, &context_data)?;
+        if let Err(e) = hooks::run_phase(
+            Phase::PostCreate,
+            &self.config.hooks.post_create.0,
+            destination,
+            &context_data,
+            inside_git,
+        ) {
+            if options.keep_on_failure {
+                return Err(e.context(format!(
+                    "Kept partially created project at '{}' (--keep-on-failure)",
+                    destination.display()
+                )));
+            }
+            return Err(rollback(e, destination, "project directory"));
+        }
 
         println!(
             "🎉 Project '{}' created successfully at {}",
@@ -138,13 +535,146 @@ impl Archetype {
         Ok(())
     }
 
+    /// `cproject init`：檔案已搬進既有目錄後執行 post_create 鉤子。
+    /// 目錄原本就存在，失敗時不會移除任何東西。
+    fn finish_init(
+        &self,
+        project_name: &str,
+        destination: &Path,
+        context: &serde_json::Value,
+        inside_git: bool,
+    ) -> Result<()> {
+        hooks::run_phase(
+            Phase::PostCreate,
+            &self.config.hooks.post_create.0,
+            destination,
+            context,
+            inside_git,
+        )
+        .with_context(|| {
+            format!(
+                "Project files were already written to '{}'",
+                destination.display()
+            )
+        })?;
+        println!(
+            "🎉 Project '{}' initialized in {}",
+            project_name,
+            destination.display()
+        );
+        Ok(())
+    }
+
+    /// 只印出將會發生的事，不寫入任何檔案、不執行鉤子
+    fn print_dry_run(
+        &self,
+        destination: &Path,
+        context: &serde_json::Value,
+        existing: Option<ExistingFiles>,
+    ) -> Result<()> {
+        println!("🧪 Dry run for archetype '{}'", self.name);
+        for layer in &self.layers {
+            println!("   from {}", layer.display());
+        }
+
+        println!("\nVariables:");
+        println!("{}", serde_json::to_string_pretty(context)?);
+
+        let inside_git = Self::is_inside_git_repo(destination)?;
+        println!("\nFiles:");
+        for file in self.plan_render(destination, context, inside_git)? {
+            let rel = file.src_rel.display();
+            let note = match existing {
+                Some(policy) if file.dest.exists() => match policy {
+                    ExistingFiles::Refuse => "  (exists: conflict)",
+                    ExistingFiles::Merge => "  (exists: kept)",
+                    ExistingFiles::Force => "  (exists: overwritten)",
+                },
+                _ => "",
+            };
+            match &file.action {
+                FileAction::Render(_) => {
+                    println!("  render  {} -> {}{}", rel, file.dest.display(), note)
+                }
+                FileAction::Copy => {
+                    println!("  copy    {} -> {}{}", rel, file.dest.display(), note)
+                }
+                FileAction::Skip(reason) => println!("  skip    {} ({})", rel, reason),
+            }
+        }
+
+        for (phase, entries) in self.config.hooks.phases() {
+            if entries.is_empty() {
+                continue;
+            }
+            println!("\nHooks ({}):", phase);
+            for hook in hooks::prepare(entries, destination, context, inside_git)? {
+                let mut notes = Vec::new();
+                if hook.shell {
+                    notes.push("shell".to_string());
+                }
+                if hook.cwd != destination {
+                    notes.push(format!("cwd: {}", hook.cwd.display()));
+                }
+                for (key, value) in &hook.env {
+                    notes.push(format!("{}={}", key, value));
+                }
+                if hook.allow_failure {
+                    notes.push("allow_failure".to_string());
+                }
+                if let Some(timeout) = hook.timeout {
+                    notes.push(format!("timeout: {}s", timeout.as_secs()));
+                }
+                if let Some(reason) = hook.skip {
+                    notes.push(format!("skipped: {}", reason));
+                }
+                if notes.is_empty() {
+                    println!("  $ {}", hook.command);
+                } else {
+                    println!("  $ {}  ({})", hook.command, notes.join("; "));
+                }
+            }
+        }
+        let vars: Vec<String> = hooks::variable_env(context).into_keys().collect();
+        println!("\nHook environment: {}", vars.join(", "));
+
+        println!("\n✅ Dry run complete; nothing was written.");
+        Ok(())
+    }
+
+    /// 確認預先提供的變數都是原型有宣告的
+    fn check_provided_vars(&self, provided: &HashMap<String, String>) -> Result<()> {
+        let mut unknown: Vec<&str> = provided
+            .keys()
+            .filter(|k| !self.config.variables.contains_key(*k))
+            .map(String::as_str)
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        let mut declared: Vec<&str> = self.config.variables.keys().map(String::as_str).collect();
+        declared.sort();
+        Err(anyhow!(
+            "Archetype '{}' does not declare variable(s): {} (declared: {})",
+            self.name,
+            unknown.join(", "),
+            if declared.is_empty() {
+                "none".to_string()
+            } else {
+                declared.join(", ")
+            }
+        ))
+    }
+
     /// 透過互動式提示收集使用者輸入的變數
     fn collect_variables_interactively(
         &self,
         project_name: &str,
         destination: &Path,
+        provided: &HashMap<String, String>,
     ) -> Result<serde_json::Value> {
-        let mut context = HashMap::new();
+        let mut context = serde_json::Map::new();
         context.insert("name".to_string(), json!(project_name));
         context.insert(
             "year".to_string(),
@@ -152,32 +682,76 @@ impl Archetype {
         );
 
         println!("Please provide the following details for your project:");
+        let mut current_group = None;
         for (key, var_info) in &self.config.variables {
             // Ensure reserved keys are not overridden by template variables
             if key == "name" || key == "year" {
                 continue;
             }
+            // 已透過 --var / --vars-file 提供的值不再提示
+            if let Some(raw) = provided.get(key) {
+                let value = var_info
+                    .parse_answer(raw)
+                    .with_context(|| format!("Invalid value for variable '{}'", key))?;
+                context.insert(key.clone(), value);
+                continue;
+            }
             // If destination is already inside a git repo, skip author/license prompts
             if (key == "author" || key == "license") && Self::is_inside_git_repo(destination)? {
                 continue;
             }
             // 簡易的互動式輸入，可以使用 `dialoguer` crate 來優化
-            println!(
-                "▶️ {} (default: {}):",
-                var_info.prompt,
-                var_info.default.as_deref().unwrap_or("")
-            );
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            let value = input.trim();
-
-            if value.is_empty() {
-                context.insert(key.clone(), json!(var_info.default));
-            } else {
-                context.insert(key.clone(), json!(value));
+            // 輸入不合法時重新提示
+            let default = var_info
+                .default_value(&context)
+                .with_context(|| format!("Invalid default for variable '{}'", key))?;
+            if var_info.group.is_some() && var_info.group != current_group {
+                println!("\n== {} ==", var_info.group.as_deref().unwrap_or_default());
+            }
+            current_group = var_info.group.clone();
+            loop {
+                println!(
+                    "▶️ {}{} (default: {}):",
+                    var_info.prompt,
+                    var_info.hint(),
+                    value_text_json(&default)
+                );
+                if let Some(help) = &var_info.help {
+                    println!("   {}", help);
+                }
+                let mut input = String::new();
+                if std::io::stdin().read_line(&mut input)? == 0 {
+                    return Err(anyhow!(
+                        "stdin was closed while prompting for '{}'; pass --defaults or --var {}=<value>",
+                        key,
+                        key
+                    ));
+                }
+                let value = input.trim();
+
+                let parsed = if value.is_empty() {
+                    Ok(default.clone())
+                } else {
+                    var_info.parse_answer(value)
+                };
+                match parsed {
+                    Ok(v) => {
+                        context.insert(key.clone(), v);
+                        break;
+                    }
+                    Err(e) => println!("  ⚠️ {}", e),
+                }
             }
         }
-        Ok(json!(context))
+        Ok(serde_json::Value::Object(context))
+    }
+
+    /// 是否有任何變數需要互動式提示（未以 --var 提供）
+    fn has_prompts(&self, provided: &HashMap<String, String>) -> bool {
+        self.config
+            .variables
+            .keys()
+            .any(|key| key != "name" && key != "year" && !provided.contains_key(key))
     }
 
     /// 使用預設值自動填入變數（非互動）
@@ -185,8 +759,9 @@ impl Archetype {
         &self,
         project_name: &str,
         destination: &Path,
+        provided: &HashMap<String, String>,
     ) -> Result<serde_json::Value> {
-        let mut context = HashMap::new();
+        let mut context = serde_json::Map::new();
         context.insert("name".to_string(), json!(project_name));
         context.insert(
             "year".to_string(),
@@ -197,22 +772,46 @@ impl Archetype {
             if key == "name" || key == "year" {
                 continue;
             }
+            // 已透過 --var / --vars-file 提供的值不再提示
+            if let Some(raw) = provided.get(key) {
+                let value = var_info
+                    .parse_answer(raw)
+                    .with_context(|| format!("Invalid value for variable '{}'", key))?;
+                context.insert(key.clone(), value);
+                continue;
+            }
             // If destination is already inside a git repo, skip author/license defaults
             if (key == "author" || key == "license") && Self::is_inside_git_repo(destination)? {
                 continue;
             }
-            context.insert(
-                key.clone(),
-                json!(var_info.default.clone().unwrap_or_default()),
-            );
+            let value = var_info
+                .default_value(&context)
+                .with_context(|| format!("Invalid default for variable '{}'", key))?;
+            context.insert(key.clone(), value);
         }
-        Ok(json!(context))
+        Ok(serde_json::Value::Object(context))
     }
 
     fn is_inside_git_repo(path: &Path) -> Result<bool> {
+        // 目的地可能尚未建立（例如 dry run），改從最近的既有上層目錄判斷
+        let Some(existing) = path
+            .ancestors()
+            .map(|p| {
+                if p.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    p
+                }
+            })
+            .find(|p| p.is_dir())
+        else {
+            return Ok(false);
+        };
         if let Ok(status) = Command::new("git")
             .args(["rev-parse", "--is-inside-work-tree"])
-            .current_dir(path)
+            .current_dir(existing)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
             .status()
         {
             return Ok(status.success());
@@ -220,98 +819,565 @@ impl Archetype {
         Ok(false)
     }
 
-    fn render_template_dir(&self, dest_path: &Path, context: &serde_json::Value) -> Result<()> {
-        let hbs = Handlebars::new();
-        let walker = walkdir::WalkDir::new(&self.template_path).into_iter();
+    /// 編譯 `[files]` 區段的 glob 規則
+    fn compile_file_rules(files: &HashMap<String, String>) -> Result<Vec<(GlobMatcher, &str)>> {
+        let mut rules = Vec::new();
+        for (pattern, condition) in files {
+            let matcher = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("Invalid glob '{}' in [files]", pattern))?
+                .compile_matcher();
+            rules.push((matcher, condition.as_str()));
+        }
+        Ok(rules)
+    }
 
-        for entry in walker.filter_map(Result::ok) {
-            let src_path = entry.path();
-            if src_path == self.template_path
-                || src_path.file_name().unwrap_or_default() == "archetype.toml"
-            {
-                continue;
+    /// 判斷模板中的相對路徑是否應該產生。
+    /// 路徑本身或任何上層目錄符合某條規則、且該條件渲染為 false 時即排除。
+    fn is_included(
+        hbs: &Handlebars,
+        rules: &[(GlobMatcher, &str)],
+        rel_path: &Path,
+        context: &serde_json::Value,
+    ) -> Result<bool> {
+        for candidate in rel_path.ancestors() {
+            if candidate.as_os_str().is_empty() {
+                break;
             }
+            for (matcher, condition) in rules {
+                if !matcher.is_match(candidate) {
+                    continue;
+                }
+                let rendered = hbs
+                    .render_template(condition, context)
+                    .with_context(|| format!("Failed to evaluate condition '{}'", condition))?;
+                if !render::is_truthy(&rendered) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// 計算每個模板檔案的目的地與處理方式（渲染、複製或略過）
+    fn plan_render(
+        &self,
+        dest_path: &Path,
+        context: &serde_json::Value,
+        inside_git: bool,
+    ) -> Result<Vec<PlannedFile>> {
+        let hbs = render::handlebars();
+        let rules = Self::compile_file_rules(&self.config.files)?;
+        let raw = compile_globs(&self.config.raw, "raw")?;
+        let mut plan = Vec::new();
 
-            let rel_path = src_path.strip_prefix(&self.template_path)?;
+        for (rel_path, src_path) in self.template_files()? {
             let rendered_rel_path_str =
-                hbs.render_template(&rel_path.to_string_lossy(), context)?;
-            let dest_file_path = dest_path.join(PathBuf::from(rendered_rel_path_str));
+                hbs.render_template(&rel_path.to_string_lossy(), context)
+                    .with_context(|| format!("Failed to render path '{}'", rel_path.display()))?;
+            let mut dest_file_path = dest_path.join(PathBuf::from(rendered_rel_path_str));
 
-            // If destination is already inside a Git repository, skip generating .gitignore
-            if dest_file_path
+            let action = if !Self::is_included(&hbs, &rules, &rel_path, context)? {
+                FileAction::Skip("excluded by [files] condition")
+            } else if dest_file_path
                 .file_name()
                 .map(|n| n == ".gitignore")
                 .unwrap_or(false)
-                && Self::is_inside_git_repo(dest_path)?
+                && inside_git
             {
-                continue;
-            }
-
-            if entry.file_type().is_dir() {
-                fs::create_dir_all(&dest_file_path)?;
+                // If destination is already inside a Git repository, skip generating .gitignore
+                FileAction::Skip("inside existing git repository")
+            } else if raw
+                .matched_path_or_any_parents(&rel_path, false)
+                .is_ignore()
+            {
+                FileAction::Copy
             } else {
-                if let Some(parent) = dest_file_path.parent() {
-                    fs::create_dir_all(parent)?;
+                // 將所有文字檔案內容當作模板渲染；二進位檔案直接複製
+                let bytes = fs::read(&src_path)?;
+                match String::from_utf8(bytes) {
+                    Ok(template_str) => {
+                        if src_path.extension().is_some_and(|e| e == "hbs") {
+                            dest_file_path = dest_file_path.with_extension("");
+                        }
+                        let rendered_content = hbs
+                            .render_template(&template_str, context)
+                            .with_context(|| {
+                                format!("Failed to render '{}'", rel_path.display())
+                            })?;
+                        FileAction::Render(rendered_content)
+                    }
+                    Err(_) => FileAction::Copy,
                 }
+            };
 
-                // 將所有文字檔案內容當作模板渲染；二進位檔案直接複製
-                let is_hbs = src_path.extension().map_or(false, |e| e == "hbs");
-                let bytes = fs::read(src_path)?;
-                if let Ok(template_str) = String::from_utf8(bytes) {
-                    let rendered_content = hbs.render_template(&template_str, context)?;
-                    let final_path = if is_hbs {
-                        dest_file_path.with_extension("")
-                    } else {
-                        dest_file_path.clone()
-                    };
-                    fs::write(final_path, rendered_content)?;
-                } else {
-                    // binary: just copy
-                    fs::copy(src_path, &dest_file_path)?;
+            plan.push(PlannedFile {
+                src_rel: rel_path,
+                src: src_path,
+                dest: dest_file_path,
+                action,
+            });
+        }
+        Ok(plan)
+    }
+
+    /// 渲染模板到目的地，回傳寫入的檔案路徑
+    fn render_template_dir(
+        &self,
+        dest_path: &Path,
+        context: &serde_json::Value,
+        inside_git: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        // 模板中的空目錄也會保留
+        for dir in self.rendered_empty_dirs(context)? {
+            fs::create_dir_all(dest_path.join(dir))?;
+        }
+        for file in self.plan_render(dest_path, context, inside_git)? {
+            if matches!(file.action, FileAction::Skip(_)) {
+                continue;
+            }
+            if let Some(parent) = file.dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match file.action {
+                FileAction::Render(content) => fs::write(&file.dest, content)?,
+                // binary: just copy
+                FileAction::Copy => {
+                    fs::copy(&file.src, &file.dest)?;
                 }
+                FileAction::Skip(_) => continue,
+            }
+            written.push(file.dest);
+        }
+        Ok(written)
+    }
+
+    /// 在記憶體中渲染整個原型（相對路徑 -> 內容），供 `cproject upgrade` 比對
+    pub(crate) fn render_in_memory(
+        &self,
+        context: &serde_json::Value,
+        inside_git: bool,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let mut files = Vec::new();
+        for file in self.plan_render(Path::new(""), context, inside_git)? {
+            let content = match file.action {
+                FileAction::Render(content) => content.into_bytes(),
+                FileAction::Copy => fs::read(&file.src)?,
+                FileAction::Skip(_) => continue,
+            };
+            files.push((file.dest, content));
+        }
+        Ok(files)
+    }
+
+    /// 原型目錄的絕對路徑
+    pub(crate) fn source(&self) -> PathBuf {
+        fs::canonicalize(&self.template_path).unwrap_or_else(|_| self.template_path.clone())
+    }
+
+    /// 原型目錄所在 git 儲存庫的 commit
+    pub(crate) fn revision(&self) -> Option<String> {
+        git_templates::revision(&self.template_path).ok()
+    }
+
+    /// 寫入 `.cproject/archetype.lock` 與渲染結果的副本，供日後 `cproject upgrade` 使用
+    fn write_lock(
+        &self,
+        project_dir: &Path,
+        context: &serde_json::Value,
+        written: &[PathBuf],
+        inside_git: bool,
+    ) -> Result<()> {
+        let mut lock = ArchetypeLock {
+            archetype: self.name.clone(),
+            source: self.source(),
+            revision: self.revision(),
+            inside_git_repo: inside_git,
+            variables: context
+                .as_object()
+                .map(|m| m.clone().into_iter().collect())
+                .unwrap_or_default(),
+            files: BTreeMap::new(),
+        };
+        for path in written {
+            let rel = path.strip_prefix(project_dir)?;
+            lock.record_file(project_dir, &lock::rel_key(rel), &fs::read(path)?)?;
+        }
+        lock.save(project_dir)
+    }
+
+    /// 補上 lock 中沒有記錄的變數（例如原型後來新增的變數）的預設值
+    pub(crate) fn fill_defaults(
+        &self,
+        variables: &mut serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        for (key, var_info) in &self.config.variables {
+            if !variables.contains_key(key) {
+                let value = var_info.default_value(variables)?;
+                variables.insert(key.clone(), value);
             }
         }
         Ok(())
     }
+}
 
-    fn run_hooks(&self, working_dir: &Path, context: &serde_json::Value) -> Result<()> {
-        let hbs = Handlebars::new();
-        // Detect if working_dir is already inside a Git repository; if so, skip git-related hooks
-        let mut skip_git = false;
-        if let Ok(status) = std::process::Command::new("git")
-            .args(["rev-parse", "--is-inside-work-tree"])
-            .current_dir(working_dir)
-            .status()
-        {
-            if status.success() {
-                skip_git = true;
-            }
-        }
-        for cmd_template in &self.config.hooks.post_create.commands {
-            let cmd_str = hbs.render_template(cmd_template, context)?;
-            println!("  -> Executing: `{}`", cmd_str);
-            let mut lexer = Shlex::new(&cmd_str);
-            let parts: Vec<String> = lexer.by_ref().collect();
-            let program = parts
-                .get(0)
-                .ok_or_else(|| anyhow!("Empty command in hooks"))?;
-            let args: Vec<&str> = parts.iter().skip(1).map(|s| s.as_str()).collect();
-
-            if skip_git && program == "git" {
-                println!("  -> Skipping git command inside existing repository");
+/// 預設值是否需要在執行時計算（環境變數、git config 或 Handlebars 模板）
+fn is_computed(default: &str) -> bool {
+    is_lookup(default) || default.contains("{{")
+}
+
+/// `env:NAME` 或 `git:key`
+fn is_lookup(default: &str) -> bool {
+    default.starts_with("env:") || default.starts_with("git:")
+}
+
+/// TOML 值的文字形式（字串不加引號）
+fn value_text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JSON 值的文字形式（字串不加引號）
+fn value_text_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `git config --get <key>`；未設定或沒有 git 時回傳 None
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// 原型中放置程式碼產生器（`cproject generate`）的目錄；不會複製到新專案中
+pub(crate) const GENERATORS_DIR: &str = "generators";
+
+/// 模板目錄中可選的忽略檔，語法同 .gitignore
+const IGNORE_FILE: &str = ".cprojectignore";
+
+/// 讀取某一層模板目錄的 `.cprojectignore`（不存在時不忽略任何檔案）
+fn layer_ignore(layer: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(layer);
+    let path = layer.join(IGNORE_FILE);
+    if path.is_file() {
+        if let Some(e) = builder.add(&path) {
+            return Err(anyhow::Error::new(e).context(format!("Invalid {}", path.display())));
+        }
+    }
+    builder
+        .build()
+        .with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// 編譯 `exclude` / `raw` 的樣式清單。與 `.cprojectignore` 同樣採用 .gitignore 語法：
+/// 不含 `/` 的樣式比對任何深度的名稱，符合的目錄會連同其內容一起比對
+fn compile_globs(patterns: &[String], section: &str) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new("");
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("Invalid glob '{}' in {}", pattern, section))?;
+    }
+    builder
+        .build()
+        .with_context(|| format!("Invalid glob in {}", section))
+}
+
+/// `cproject init` 的暫存目錄，位於目的地的 `.cproject/` 之中
+const INIT_STAGING_DIR: &str = "staging";
+
+/// 暫存目錄與目的地位於同一個檔案系統，確保最後的 rename 不會跨檔案系統。
+/// 新專案的暫存目錄在目的地旁邊；`cproject init` 則放在目的地的 `.cproject/staging`，
+/// 不需要上層目錄的寫入權限
+fn staging_dir(destination: &Path, existing: bool) -> PathBuf {
+    if existing {
+        return destination.join(lock::LOCK_DIR).join(INIT_STAGING_DIR);
+    }
+    let name = destination
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    destination.with_file_name(format!(".{}.cproject-staging-{}", name, std::process::id()))
+}
+
+/// 將暫存目錄中的檔案逐一搬進既有目錄，依 `policy` 處理已存在的檔案。
+/// `.cproject/` 中的 lock 一律以這次的結果為準；`empty_dirs` 是模板中要保留的空目錄。
+fn move_into(
+    staging: &Path,
+    destination: &Path,
+    policy: ExistingFiles,
+    empty_dirs: &[PathBuf],
+) -> Result<()> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(staging)
+        .min_depth(1)
+        .sort_by_file_name()
+    {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(staging)?.to_path_buf();
+        // 目的地中的這個位置就是暫存目錄本身，不能搬到自己裡面
+        if rel.starts_with(Path::new(lock::LOCK_DIR).join(INIT_STAGING_DIR)) {
+            continue;
+        }
+        if !entry.file_type().is_dir() {
+            files.push(rel);
+        }
+    }
+    let is_lock = |rel: &Path| rel.starts_with(lock::LOCK_DIR);
+
+    let conflicts: Vec<&PathBuf> = files
+        .iter()
+        .filter(|rel| destination.join(rel).exists())
+        .collect();
+    if policy == ExistingFiles::Refuse && !conflicts.is_empty() {
+        let list: Vec<String> = conflicts
+            .iter()
+            .map(|rel| format!("  {}", rel.display()))
+            .collect();
+        return Err(anyhow!(
+            "These files already exist in '{}':\n{}\nUse --merge to keep them or --force to overwrite them",
+            destination.display(),
+            list.join("\n")
+        ));
+    }
+
+    // 保留模板中的空目錄
+    for rel in empty_dirs {
+        fs::create_dir_all(destination.join(rel))?;
+    }
+    for rel in &files {
+        let target = destination.join(rel);
+        if target.exists() && !is_lock(rel) {
+            if policy == ExistingFiles::Merge {
+                println!("  -> Keeping existing {}", rel.display());
                 continue;
             }
+            println!("  -> Overwriting {}", rel.display());
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(staging.join(rel), &target)
+            .with_context(|| format!("Failed to move '{}' into place", rel.display()))?;
+    }
+    Ok(())
+}
+
+/// 移除暫存目錄；`cproject init` 時一併移除因此建立、仍是空的 `.cproject/`
+fn rollback_staging(err: anyhow::Error, staging: &Path, existing: bool) -> anyhow::Error {
+    let err = rollback(err, staging, "staging directory");
+    if existing {
+        if let Some(lock_dir) = staging.parent() {
+            let _ = fs::remove_dir(lock_dir);
+        }
+    }
+    err
+}
 
-            let status = Command::new(program)
-                .args(args)
-                .current_dir(working_dir)
-                .status()
-                .with_context(|| format!("Failed to execute hook command: {}", cmd_str))?;
+/// 移除建立到一半的目錄，並在錯誤中註明清理了什麼
+fn rollback(err: anyhow::Error, dir: &Path, what: &str) -> anyhow::Error {
+    match fs::remove_dir_all(dir) {
+        Ok(()) => err.context(format!(
+            "Project creation rolled back; removed {} '{}'",
+            what,
+            dir.display()
+        )),
+        Err(rm_err) => err.context(format!(
+            "Project creation failed and {} '{}' could not be removed: {}",
+            what,
+            dir.display(),
+            rm_err
+        )),
+    }
+}
 
-            if !status.success() {
-                return Err(anyhow!("Hook command failed: {}", cmd_str));
+/// `instantiate` 的行為選項
+#[derive(Debug, Default)]
+pub struct InstantiateOptions {
+    /// 不提示，全部使用預設值
+    pub use_defaults: bool,
+    /// 只印出將產生的檔案與鉤子，不實際寫入
+    pub dry_run: bool,
+    /// 預先提供的變數值（來自 --var 與 --vars-file），這些變數不會再提示
+    pub vars: HashMap<String, String>,
+    /// 鉤子失敗時保留已建立的專案目錄，而不是整個移除
+    pub keep_on_failure: bool,
+    /// 渲染到既有目錄（`cproject init`），並以此處理已存在的檔案；`None` 時目的地不可存在
+    pub existing: Option<ExistingFiles>,
+}
+
+/// `cproject init` 遇到目錄中已存在的檔案時的處理方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExistingFiles {
+    /// 任何檔案已存在就中止，不寫入任何東西
+    #[default]
+    Refuse,
+    /// 保留既有檔案，只加入缺少的檔案
+    Merge,
+    /// 以原型的版本覆寫既有檔案
+    Force,
+}
+
+/// 合併 `--vars-file`（TOML 或 JSON）與 `--var key=value`；後者優先
+pub fn parse_provided_vars(
+    assignments: &[String],
+    vars_file: Option<&Path>,
+) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    if let Some(path) = vars_file {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read vars file {}", path.display()))?;
+        let table: serde_json::Map<String, serde_json::Value> =
+            if path.extension().is_some_and(|e| e == "json") {
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse JSON from {}", path.display()))?
+            } else {
+                toml::from_str(&content)
+                    .with_context(|| format!("Failed to parse TOML from {}", path.display()))?
+            };
+        for (key, value) in table {
+            let text = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Bool(_) | serde_json::Value::Number(_) => value.to_string(),
+                _ => {
+                    return Err(anyhow!(
+                        "Variable '{}' in {} must be a string, boolean or number",
+                        key,
+                        path.display()
+                    ))
+                }
+            };
+            vars.insert(key, text);
+        }
+    }
+    for assignment in assignments {
+        let (key, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --var '{}': expected key=value", assignment))?;
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+/// 單一模板檔案的處理計畫
+pub(crate) struct PlannedFile {
+    /// 模板中的相對路徑（未渲染）
+    pub(crate) src_rel: PathBuf,
+    pub(crate) src: PathBuf,
+    pub(crate) dest: PathBuf,
+    pub(crate) action: FileAction,
+}
+
+pub(crate) enum FileAction {
+    /// 文字檔：渲染後的內容
+    Render(String),
+    /// 二進位檔：原樣複製
+    Copy,
+    /// 不產生，附上原因
+    Skip(&'static str),
+}
+
+/// 一個模板位置，以及它的來源說明
+pub struct TemplateRoot {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// 依優先順序列出所有模板位置；git 來源會解析為本機快取路徑
+pub fn template_roots(app_config: &Config) -> Result<Vec<TemplateRoot>> {
+    let mut roots = Vec::new();
+    for loc in &app_config.templates.locations {
+        match loc {
+            TemplateLocation::Path(path) => roots.push(TemplateRoot {
+                path: path.clone(),
+                source: "config".to_string(),
+            }),
+            TemplateLocation::Git(git) => {
+                let path = git_templates::ensure(git).with_context(|| {
+                    format!("Failed to prepare template repository {}", git.git)
+                })?;
+                let source = match &git.reference {
+                    Some(r) => format!("git {}@{}", git.git, r),
+                    None => format!("git {}", git.git),
+                };
+                roots.push(TemplateRoot { path, source });
             }
         }
-        Ok(())
     }
+    // 將內建模板位置加入搜尋路徑
+    // 1) 相對於目前工作目錄
+    roots.push(TemplateRoot {
+        path: PathBuf::from("./templates"),
+        source: "current directory".to_string(),
+    });
+    // 2) 編譯進執行檔的內建原型（優先順序最低，無論在哪台機器執行都可找到）
+    roots.push(TemplateRoot {
+        path: embedded::root()?,
+        source: "built-in".to_string(),
+    });
+    Ok(roots)
+}
+
+/// 在模板位置中尋找原型名稱對應的目錄
+pub fn resolve_template_path(
+    app_config: &Config,
+    roots: &[TemplateRoot],
+    name: &str,
+) -> Option<PathBuf> {
+    let candidates = candidate_paths(app_config, name);
+    roots.iter().find_map(|root| {
+        candidates
+            .iter()
+            .map(|(rel, _)| root.path.join(rel))
+            .find(|p| p.exists())
+    })
+}
+
+/// 原型名稱對應的候選相對路徑（依優先順序），附上每個候選的由來
+pub fn candidate_paths(app_config: &Config, name: &str) -> Vec<(String, &'static str)> {
+    let mut candidates = Vec::new();
+    if let Some(mapped) = app_config.archetypes.get(name) {
+        candidates.push((mapped.clone(), "config mapping"));
+    }
+    // Built-in aliases for convenience when config is missing
+    if let Some(alias) = builtin_alias(name) {
+        candidates.push((alias.to_string(), "built-in alias"));
+    }
+    // Also allow direct folder name usage
+    candidates.push((name.to_string(), "direct name"));
+    candidates
+}
+
+/// 內建別名 -> 內建原型
+pub const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("app", "default/executable"),
+    ("exe", "default/executable"),
+    ("executable", "default/executable"),
+    ("lib", "default/library"),
+    ("library", "default/library"),
+    ("static", "default/library"),
+    ("shared", "default/shared"),
+    ("shared-lib", "default/shared"),
+    ("header-only", "default/header-only"),
+    ("header", "default/header-only"),
+    ("c", "default/c"),
+];
+
+/// 內建別名
+pub fn builtin_alias(name: &str) -> Option<&'static str> {
+    BUILTIN_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, target)| *target)
 }
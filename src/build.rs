@@ -1,81 +1,237 @@
 use anyhow::{anyhow, Context, Result};
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use std::env;
-use std::{fs, path::Path, process::Command};
+use std::path::{Path, PathBuf};
+use std::{fs, process::Command};
 
 // Import our new Config struct
 use crate::config::Config;
+use crate::manifest::Profile;
+use crate::sanitize;
 use crate::util;
 
-/// Configure and build the project using CMake.
+/// Configure and build the project using CMake with the given profile.
 /// Now accepts the application config to find the toolchain.
-pub fn cmake_build(config: &Config, debug: bool, build_tests: bool) -> Result<()> {
-    let build_type = if debug { "Debug" } else { "Release" };
-    let build_dir = format!("build/{}", if debug { "debug" } else { "release" });
-    fs::create_dir_all(&build_dir)?;
+/// The configure step is skipped when its inputs are unchanged, unless `reconfigure` is set.
+pub fn cmake_build(
+    config: &Config,
+    profile: &Profile,
+    build_tests: bool,
+    reconfigure: bool,
+) -> Result<()> {
+    let build_type = &profile.build_type;
+    let build_dir = &profile.build_dir;
+    fs::create_dir_all(build_dir)?;
+
+    // `test` always needs the tests; otherwise a profile may turn them on itself
+    let mut defines = profile.defines.clone();
+    if build_tests {
+        defines.insert("BUILD_TESTS".to_string(), "ON".to_string());
+    } else {
+        defines
+            .entry("BUILD_TESTS".to_string())
+            .or_insert_with(|| "OFF".to_string());
+    }
 
     // Configure
     let mut cfg = Command::new("cmake");
-    cfg.args(["-S", ".", "-B", &build_dir])
+    cfg.args(["-S", "."])
+        .arg("-B")
+        .arg(build_dir)
         .arg(format!("-DCMAKE_BUILD_TYPE={build_type}"))
-        .arg(format!(
-            "-DBUILD_TESTS={}",
-            if build_tests { "ON" } else { "OFF" }
-        ));
+        .envs(&profile.env);
+    for (key, value) in &defines {
+        cfg.arg(format!("-D{key}={value}"));
+    }
+    if !profile.cxx_flags.is_empty() {
+        cfg.arg(format!("-DCMAKE_CXX_FLAGS={}", profile.cxx_flags.join(" ")));
+    }
 
     // If we later add C++ standard to Config, we can pass -DCMAKE_CXX_STANDARD here.
 
-    // Prefer explicit config, then env, then common defaults
-    let mut candidate_roots: Vec<std::path::PathBuf> = Vec::new();
-    if let Some(v) = &config.vcpkg_root {
-        candidate_roots.push(v.clone());
-    }
-    if let Ok(env_root) = env::var("VCPKG_ROOT") {
-        candidate_roots.push(env_root.into());
-    }
-    if let Some(home) = dirs::home_dir() {
-        candidate_roots.push(home.join(".local/share/vcpkg"));
+    if let Some(root) = vcpkg_root(config) {
+        cfg.arg(format!(
+            "-DCMAKE_TOOLCHAIN_FILE={}",
+            root.join(VCPKG_TOOLCHAIN).display()
+        ));
     }
-    candidate_roots.push(std::path::PathBuf::from("vcpkg"));
 
-    for root in candidate_roots {
-        let toolchain_file = root.join("scripts/buildsystems/vcpkg.cmake");
-        if toolchain_file.exists() {
-            cfg.arg(format!(
-                "-DCMAKE_TOOLCHAIN_FILE={}",
-                toolchain_file.display()
-            ));
-            break;
-        }
+    // The generator is left out of the fingerprint: switching it clears the cache anyway
+    let fingerprint = configure_fingerprint(&cfg)?;
+    if let Some(generator) = select_generator(config, profile)? {
+        cfg.args(["-G", &generator]);
     }
-
-    let st = cfg.status().context("Failed to invoke cmake (configure)")?;
-    if !st.success() {
-        return Err(anyhow!("cmake configure failed"));
+    let fingerprint_file = build_dir.join(FINGERPRINT_FILE);
+    let up_to_date = configure_stamp(&fingerprint, build_dir)
+        .is_some_and(|stamp| fs::read_to_string(&fingerprint_file).is_ok_and(|f| f == stamp));
+    if up_to_date && !reconfigure {
+        println!("⏭️ Configuration is up to date; skipping cmake configure.");
+    } else {
+        // A failed configure must not leave a stale fingerprint behind
+        let _ = fs::remove_file(&fingerprint_file);
+        let st = cfg.status().context("Failed to invoke cmake (configure)")?;
+        if !st.success() {
+            return Err(anyhow!("cmake configure failed"));
+        }
+        if let Some(stamp) = configure_stamp(&fingerprint, build_dir) {
+            fs::write(&fingerprint_file, stamp)?;
+        }
     }
 
     // Build
-    println!("🔨 Building project in '{}' mode...", build_type);
+    println!(
+        "🔨 Building project with profile '{}' ({})...",
+        profile.name, build_type
+    );
     let st = Command::new("cmake")
-        .args(["--build", &build_dir, "--"])
-        // A simple improvement: use multiple cores for faster builds.
-        .arg(format!("-j{}", num_cpus::get()))
+        .arg("--build")
+        .arg(build_dir)
+        .envs(&profile.env)
+        // Use multiple cores; `--parallel` works with every generator.
+        .arg("--parallel")
+        .arg(num_cpus::get().to_string())
         .status()
         .context("Failed to invoke cmake --build")?;
     if !st.success() {
         return Err(anyhow!("cmake build failed"));
     }
+    // The build may re-run cmake and rewrite the cache itself; that is not an outside change
+    if let Some(stamp) = configure_stamp(&fingerprint, build_dir) {
+        fs::write(&fingerprint_file, stamp)?;
+    }
 
     println!("✅ Build complete.");
     Ok(())
 }
 
+/// Pick the CMake generator for the profile's build directory.
+///
+/// An explicit choice (`--generator`, the profile, then the `generator` config key)
+/// wins; otherwise an existing build directory keeps the generator it was configured
+/// with, and a fresh one prefers Ninja when it is installed. Switching generators on
+/// an existing cache clears it first, since CMake refuses to change generators in place.
+fn select_generator(config: &Config, profile: &Profile) -> Result<Option<String>> {
+    let build_dir = &profile.build_dir;
+    let cached = cached_generator(build_dir);
+    let requested = profile
+        .generator
+        .clone()
+        .or_else(|| config.generator.clone());
+    Ok(match (requested, cached) {
+        (Some(requested), Some(cached)) if requested != cached => {
+            println!(
+                "♻️ '{}' was configured with '{}'; reconfiguring from scratch for '{}'...",
+                build_dir.display(),
+                cached,
+                requested
+            );
+            fs::remove_file(build_dir.join("CMakeCache.txt"))?;
+            let cmake_files = build_dir.join("CMakeFiles");
+            if cmake_files.exists() {
+                fs::remove_dir_all(cmake_files)?;
+            }
+            Some(requested)
+        }
+        (Some(requested), _) => Some(requested),
+        (None, Some(cached)) => Some(cached),
+        (None, None) => which::which("ninja").ok().map(|_| "Ninja".to_string()),
+    })
+}
+
+/// The generator recorded in an existing build directory's CMakeCache.txt.
+fn cached_generator(build_dir: &Path) -> Option<String> {
+    let cache = fs::read_to_string(build_dir.join("CMakeCache.txt")).ok()?;
+    cache
+        .lines()
+        .find_map(|line| line.strip_prefix("CMAKE_GENERATOR:INTERNAL="))
+        .map(str::to_string)
+}
+
+/// Where the configure fingerprint is stored inside the build directory.
+const FINGERPRINT_FILE: &str = ".cproject-configure";
+
+/// Hash everything that affects `cmake` configure: the command line apart from the
+/// generator (it includes the toolchain path), its environment, the top-level CMakeLists.txt, cmake/*.cmake
+/// and vcpkg.json.
+fn configure_fingerprint(cfg: &Command) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for arg in cfg.get_args() {
+        hasher.update(arg.as_encoded_bytes());
+        hasher.update([0]);
+    }
+    for (key, value) in cfg.get_envs() {
+        hasher.update(key.as_encoded_bytes());
+        hasher.update([0]);
+        hasher.update(value.map(|v| v.as_encoded_bytes()).unwrap_or_default());
+        hasher.update([0]);
+    }
+
+    let mut inputs = vec![PathBuf::from("CMakeLists.txt"), PathBuf::from("vcpkg.json")];
+    if let Ok(entries) = fs::read_dir("cmake") {
+        let mut modules: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "cmake"))
+            .collect();
+        modules.sort();
+        inputs.extend(modules);
+    }
+    for input in inputs {
+        // Missing files hash differently from empty ones, so adding vcpkg.json counts
+        if let Ok(content) = fs::read(&input) {
+            hasher.update(input.as_os_str().as_encoded_bytes());
+            hasher.update([0]);
+            hasher.update(&content);
+        }
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// The configure fingerprint together with a hash of the CMakeCache.txt cproject left
+/// behind, so a cache rewritten by something else (`cmake --preset`, `ccmake`, another
+/// tool pointed at the same directory) forces a fresh configure. `None` without a cache.
+fn configure_stamp(fingerprint: &str, build_dir: &Path) -> Option<String> {
+    let cache = fs::read(build_dir.join("CMakeCache.txt")).ok()?;
+    let cache_hash: String = Sha256::digest(cache)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Some(format!("{}\n{}\n", fingerprint, cache_hash))
+}
+
+/// The vcpkg CMake toolchain, relative to the vcpkg root.
+pub(crate) const VCPKG_TOOLCHAIN: &str = "scripts/buildsystems/vcpkg.cmake";
+
+/// Locate a vcpkg checkout that provides the CMake toolchain.
+/// Prefer explicit config, then env, then common defaults.
+pub(crate) fn vcpkg_root(config: &Config) -> Option<PathBuf> {
+    let mut candidate_roots: Vec<PathBuf> = Vec::new();
+    if let Some(v) = &config.vcpkg_root {
+        candidate_roots.push(v.clone());
+    }
+    if let Ok(env_root) = env::var("VCPKG_ROOT") {
+        candidate_roots.push(env_root.into());
+    }
+    if let Some(home) = dirs::home_dir() {
+        candidate_roots.push(home.join(".local/share/vcpkg"));
+    }
+    candidate_roots.push(PathBuf::from("vcpkg"));
+
+    candidate_roots
+        .into_iter()
+        .find(|root| root.join(VCPKG_TOOLCHAIN).exists())
+}
+
 /// Build and run the project's main executable.
-pub fn run_exe(_config: &Config, debug: bool) -> Result<()> {
+pub fn run_exe(_config: &Config, profile: &Profile) -> Result<()> {
     // Accept config for future use
     let name = util::project_name_from_cmakelists(".")?;
-    let build_mode = if debug { "debug" } else { "release" };
-    let build_dir = Path::new("build").join(build_mode);
+    let build_dir = &profile.build_dir;
 
     // 1. 定義主要執行檔和備用執行檔的路徑
     // 主要執行檔：適用於 executable 專案 (e.g., build/release/MyApp)
@@ -88,6 +244,14 @@ pub fn run_exe(_config: &Config, debug: bool) -> Result<()> {
         primary_exe_path
     } else if fallback_exe_path.exists() {
         fallback_exe_path
+    } else if let Some(lib) = library_artifact(build_dir, &name) {
+        // 函式庫專案（static / shared）沒有範例程式時，說明原因而不是只回報找不到
+        return Err(anyhow!(
+            "'{}' is a library ({}) without an example executable; add a '{}_example' target to run",
+            name,
+            lib.display(),
+            name
+        ));
     } else {
         // 3. 如果兩個都找不到，回傳一個更清楚的錯誤訊息
         return Err(anyhow!(
@@ -102,6 +266,8 @@ pub fn run_exe(_config: &Config, debug: bool) -> Result<()> {
     println!("------------------------------------------");
 
     let st = Command::new(&exe_to_run)
+        .envs(sanitize::runtime_env(profile))
+        .envs(&profile.env)
         .status()
         .with_context(|| format!("Failed to run executable at {}", exe_to_run.display()))?;
 
@@ -113,15 +279,31 @@ pub fn run_exe(_config: &Config, debug: bool) -> Result<()> {
     Ok(())
 }
 
+/// Find the library a library project builds (static or shared, any platform naming).
+fn library_artifact(build_dir: &Path, name: &str) -> Option<PathBuf> {
+    [
+        format!("lib{name}.a"),
+        format!("lib{name}.so"),
+        format!("lib{name}.dylib"),
+        format!("{name}.lib"),
+        format!("{name}.dll"),
+    ]
+    .into_iter()
+    .map(|file| build_dir.join(file))
+    .find(|path| path.exists())
+}
+
 /// Build and run the project's tests using CTest.
-pub fn run_tests(_config: &Config) -> Result<()> {
-    // Tests are almost always run in Debug mode.
-    let test_dir = "build/debug";
+pub fn run_tests(_config: &Config, profile: &Profile) -> Result<()> {
+    // Tests are almost always run in Debug mode (the `debug` profile).
+    let test_dir = &profile.build_dir;
 
     // Prefer running gtest test binary directly if present to get gtest-style output
-    let gtest_bin = Path::new(test_dir).join("run_tests");
+    let gtest_bin = test_dir.join("run_tests");
     if gtest_bin.exists() {
         let status = Command::new(&gtest_bin)
+            .envs(sanitize::runtime_env(profile))
+            .envs(&profile.env)
             .status()
             .with_context(|| format!("Failed to run {}", gtest_bin.display()))?;
         if !status.success() {
@@ -133,6 +315,7 @@ pub fn run_tests(_config: &Config) -> Result<()> {
     // Otherwise, list tests via ctest and run individually in a minimal format
     let list_output = Command::new("ctest")
         .current_dir(test_dir)
+        .envs(&profile.env)
         .args(["-N"]) // list without running
         .output()
         .context("Failed to invoke ctest -N")?;
@@ -154,6 +337,8 @@ pub fn run_tests(_config: &Config) -> Result<()> {
     for name in test_names {
         let status = Command::new("ctest")
             .current_dir(test_dir)
+            .envs(sanitize::runtime_env(profile))
+            .envs(&profile.env)
             .args(["-R", &name, "-Q"]) // quiet
             .status()
             .with_context(|| format!("Failed to invoke ctest for test {name}"))?;
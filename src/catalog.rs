@@ -0,0 +1,230 @@
+// src/catalog.rs
+
+use crate::archetype::{self, Archetype, ArchetypeConfig, TemplateRoot, VariableType};
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 在某個模板位置中找到的原型
+pub(crate) struct Entry {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    description: String,
+}
+
+/// 列出某個模板位置下所有含 archetype.toml 的目錄
+pub(crate) fn scan(root: &TemplateRoot) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    if !root.path.is_dir() {
+        return entries;
+    }
+    let walker = walkdir::WalkDir::new(&root.path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git");
+    for entry in walker.filter_map(Result::ok) {
+        if entry.file_name() != "archetype.toml" {
+            continue;
+        }
+        let Some(dir) = entry.path().parent() else {
+            continue;
+        };
+        let name = dir
+            .strip_prefix(&root.path)
+            .unwrap_or(dir)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let description = match ArchetypeConfig::read(dir) {
+            Ok(config) => config.description,
+            Err(e) => format!("⚠️ invalid archetype.toml: {:#}", e),
+        };
+        entries.push(Entry {
+            name,
+            path: dir.to_path_buf(),
+            description,
+        });
+    }
+    entries
+}
+
+/// `cproject archetype list`
+pub fn list(config: &Config) -> Result<()> {
+    let roots = archetype::template_roots(config)?;
+    let scanned: Vec<(&TemplateRoot, Vec<Entry>)> =
+        roots.iter().map(|root| (root, scan(root))).collect();
+
+    let width = scanned
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|e| e.name.len()))
+        .max()
+        .unwrap_or(0);
+
+    // 同名原型以第一個找到的為準，其餘標示為被遮蔽
+    let mut seen: HashMap<&str, &Path> = HashMap::new();
+    println!("📚 Available archetypes:");
+    for (root, entries) in &scanned {
+        if entries.is_empty() {
+            continue;
+        }
+        println!("\n  [{}] {}", root.source, root.path.display());
+        for entry in entries {
+            let shadowed = seen.get(entry.name.as_str());
+            println!(
+                "    {:<width$}  {}",
+                entry.name,
+                entry.description,
+                width = width
+            );
+            match shadowed {
+                Some(by) => println!(
+                    "    {:<width$}  (shadowed by {})",
+                    "",
+                    by.display(),
+                    width = width
+                ),
+                None => {
+                    seen.insert(&entry.name, &entry.path);
+                }
+            }
+        }
+    }
+
+    let mut aliases: Vec<(String, String)> = config
+        .archetypes
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for (alias, target) in archetype::BUILTIN_ALIASES {
+        if !config.archetypes.contains_key(*alias) {
+            aliases.push((alias.to_string(), target.to_string()));
+        }
+    }
+    aliases.sort();
+    println!("\n  Aliases:");
+    for (alias, target) in aliases {
+        println!("    {} -> {}", alias, target);
+    }
+    Ok(())
+}
+
+/// `cproject archetype show <name>`
+pub fn show(config: &Config, name: &str) -> Result<()> {
+    let archetype = Archetype::load(config, name)?;
+    let cfg = &archetype.config;
+    println!("📦 {}", archetype.name);
+    println!("   {}", cfg.description);
+    println!("   Path: {}", archetype.template_path.display());
+    if archetype.layers.len() > 1 {
+        println!("   Layers (base first):");
+        for layer in &archetype.layers {
+            println!("     - {}", layer.display());
+        }
+    }
+
+    println!("\nVariables:");
+    if cfg.variables.is_empty() {
+        println!("  (none)");
+    }
+    for (key, var) in &cfg.variables {
+        let mut details = vec![format!("{:?}", var.var_type).to_lowercase()];
+        if let Some(default) = var.default_text() {
+            details.push(format!("default: {}", default));
+        }
+        if var.var_type == VariableType::Choice {
+            details.push(format!("choices: {}", var.choices.join(", ")));
+        }
+        if let Some(pattern) = &var.pattern {
+            details.push(format!("pattern: {}", pattern));
+        }
+        if let Some(group) = &var.group {
+            details.push(format!("group: {}", group));
+        }
+        println!("  {} — {} ({})", key, var.prompt, details.join("; "));
+        if let Some(help) = &var.help {
+            println!("      {}", help);
+        }
+    }
+
+    if !cfg.files.is_empty() {
+        println!("\nConditional files:");
+        let mut rules: Vec<_> = cfg.files.iter().collect();
+        rules.sort();
+        for (glob, condition) in rules {
+            println!("  {} if {}", glob, condition);
+        }
+    }
+
+    if !cfg.exclude.is_empty() {
+        println!("\nExcluded: {}", cfg.exclude.join(", "));
+    }
+    if !cfg.raw.is_empty() {
+        println!("\nCopied verbatim: {}", cfg.raw.join(", "));
+    }
+
+    let generators = archetype.generator_kinds();
+    if !generators.is_empty() {
+        println!("\nGenerators: {}", generators.join(", "));
+    }
+
+    for (phase, entries) in cfg.hooks.phases() {
+        if entries.is_empty() {
+            continue;
+        }
+        println!("\nHooks ({}):", phase);
+        for hook in entries {
+            match &hook.when {
+                Some(when) => println!("  $ {}  (when {})", hook.run, when),
+                None => println!("  $ {}", hook.run),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `cproject archetype which <name>`：說明 `Archetype::load` 的解析過程
+pub fn which(config: &Config, name: &str) -> Result<()> {
+    let candidates = archetype::candidate_paths(config, name);
+    let roots = archetype::template_roots(config)?;
+
+    println!("🔎 Resolving archetype '{}'", name);
+    println!("\nCandidates (in order):");
+    for (i, (rel, reason)) in candidates.iter().enumerate() {
+        println!("  {}. {} ({})", i + 1, rel, reason);
+    }
+
+    println!("\nSearch locations (in order):");
+    let mut selected: Option<PathBuf> = None;
+    let mut shadowed: Vec<PathBuf> = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        println!("  {}. {} [{}]", i + 1, root.path.display(), root.source);
+        for (rel, _) in &candidates {
+            let path = root.path.join(rel);
+            if !path.exists() {
+                println!("       ✗ {}", rel);
+            } else if selected.is_none() {
+                println!("       ✓ {}  <- selected", rel);
+                selected = Some(path);
+            } else {
+                println!("       ✓ {}  (not used)", rel);
+                shadowed.push(path);
+            }
+        }
+    }
+
+    match selected {
+        Some(path) => {
+            println!("\n✅ '{}' resolves to {}", name, path.display());
+            for other in shadowed {
+                println!("⚠️ {} is shadowed by {}", other.display(), path.display());
+            }
+        }
+        None => {
+            return Err(anyhow!(
+                "Could not find template directory for archetype '{}'",
+                name
+            ))
+        }
+    }
+    Ok(())
+}
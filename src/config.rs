@@ -12,6 +12,9 @@ use std::path::PathBuf;
 pub struct Config {
     pub vcpkg_root: Option<PathBuf>,
 
+    /// 預設的 CMake generator（例如 "Ninja"）；未設定時若有 ninja 則優先使用
+    pub generator: Option<String>,
+
     #[serde(default)]
     pub templates: Templates,
 
@@ -23,7 +26,25 @@ pub struct Config {
 #[derive(Deserialize, Debug, Default)]
 pub struct Templates {
     #[serde(default)]
-    pub locations: Vec<PathBuf>,
+    pub locations: Vec<TemplateLocation>,
+}
+
+/// 單一模板位置：本機路徑，或以 git 儲存庫為來源
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TemplateLocation {
+    Path(PathBuf),
+    Git(GitLocation),
+}
+
+/// `{ git = "...", ref = "v2", subdir = "cpp" }`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GitLocation {
+    pub git: String,
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    pub subdir: Option<PathBuf>,
 }
 
 impl Config {
@@ -35,14 +56,8 @@ impl Config {
 
         let config_path = if from_current_dir.exists() {
             Some(from_current_dir)
-        } else if let Some(home_path) = from_home {
-            if home_path.exists() {
-                Some(home_path)
-            } else {
-                None
-            }
         } else {
-            None
+            from_home.filter(|home_path| home_path.exists())
         };
 
         if let Some(path) = config_path {
@@ -76,6 +91,9 @@ impl Config {
         }
 
         for loc in self.templates.locations.iter_mut() {
+            let TemplateLocation::Path(loc) = loc else {
+                continue;
+            };
             if loc.starts_with("~") {
                 *loc = shellexpand::tilde(loc.to_str().unwrap())
                     .into_owned()
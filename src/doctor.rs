@@ -8,6 +8,11 @@ pub fn run() -> Result<()> {
     check("cmake")?;
     check("bash")?;
     check("vcpkg")?;
+    if which("ninja").is_ok() {
+        println!("• ninja ✓ (used as the default CMake generator)");
+    } else {
+        println!("• ninja not found (CMake's default generator will be used)");
+    }
 
     if let Ok(root) = env::var("VCPKG_ROOT") {
         println!("• VCPKG_ROOT = {}", root);
@@ -0,0 +1,111 @@
+// src/embedded.rs
+
+use crate::archetype::{self, ArchetypeConfig};
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use rust_embed::RustEmbed;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 編譯進執行檔的內建原型（templates/default/**）
+#[derive(RustEmbed)]
+#[folder = "templates/default/"]
+#[prefix = "default/"]
+struct BuiltinTemplates;
+
+/// 內建原型的內容雜湊；內容改變（例如升級 cproject）時會解開到新的目錄
+fn content_hash() -> String {
+    let mut names: Vec<_> = BuiltinTemplates::iter().collect();
+    names.sort();
+    let mut hasher = Sha256::new();
+    for name in names {
+        if let Some(file) = BuiltinTemplates::get(&name) {
+            hasher.update(name.as_bytes());
+            hasher.update(file.metadata.sha256_hash());
+        }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 將內建原型解開到 <user cache dir>/cproject/builtin/<hash>，回傳該目錄。
+/// 其他程式碼因此可以把它當作一般的模板位置處理。
+pub fn root() -> Result<PathBuf> {
+    let cache = dirs::cache_dir().ok_or_else(|| anyhow!("cannot resolve user cache directory"))?;
+    let dir = cache.join("cproject").join("builtin").join(content_hash());
+    if dir.is_dir() {
+        return Ok(dir);
+    }
+
+    // 先寫到暫存目錄再改名，避免中斷時留下不完整的內容
+    let staging = dir.with_extension(format!("tmp-{}", std::process::id()));
+    write_files(&staging, "")
+        .with_context(|| format!("Failed to unpack built-in templates to {}", dir.display()))?;
+    if let Err(e) = fs::rename(&staging, &dir) {
+        let _ = fs::remove_dir_all(&staging);
+        // 另一個行程可能剛好先完成
+        if !dir.is_dir() {
+            return Err(e).context("Failed to unpack built-in templates");
+        }
+    }
+    Ok(dir)
+}
+
+/// 路徑是否位於內建原型解開的快取目錄中（可能是舊版 cproject 的內容）
+pub fn is_builtin_path(path: &Path) -> bool {
+    dirs::cache_dir()
+        .map(|cache| path.starts_with(cache.join("cproject").join("builtin")))
+        .unwrap_or(false)
+}
+
+/// 將名稱以 `prefix` 開頭的內建檔案寫到 `dest`（去掉 prefix）
+fn write_files(dest: &Path, prefix: &str) -> Result<usize> {
+    let mut count = 0;
+    for name in BuiltinTemplates::iter() {
+        let Some(rel) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let file = BuiltinTemplates::get(&name).expect("embedded file listed by iter()");
+        let path = dest.join(rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, file.data)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// `cproject archetype export <name> <dir>`：將內建原型寫出成可自行修改的模板目錄
+pub fn export(config: &Config, name: &str, dir: &Path) -> Result<()> {
+    let rel = archetype::candidate_paths(config, name)
+        .into_iter()
+        .map(|(rel, _)| rel.trim_end_matches('/').to_string())
+        .find(|rel| BuiltinTemplates::get(&format!("{}/archetype.toml", rel)).is_some())
+        .ok_or_else(|| anyhow!("'{}' is not a built-in archetype", name))?;
+
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        return Err(anyhow!("Directory '{}' is not empty", dir.display()));
+    }
+    let count = write_files(dir, &format!("{}/", rel))?;
+
+    println!(
+        "✅ Exported built-in archetype '{}' ({} files) to {}",
+        rel,
+        count,
+        dir.display()
+    );
+    if let Some(parent) = ArchetypeConfig::read(dir)?.extends {
+        println!(
+            "   It extends '{}'; export that archetype too to customize the shared files.",
+            parent
+        );
+    }
+    println!("   Add its parent directory to [templates] locations to use it.");
+    Ok(())
+}
@@ -0,0 +1,254 @@
+// src/extract.rs
+
+use crate::config::{Config, TemplateLocation};
+use crate::lock;
+use crate::render;
+use crate::util;
+use anyhow::{anyhow, Context, Result};
+use heck::ToShoutySnakeCase;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `cproject archetype extract <project-dir> <archetype-name>`：
+/// 以現有專案產生一個新的原型，專案名稱以 `{{name}}` 取代。
+pub fn run(
+    config: &Config,
+    project_dir: &Path,
+    archetype_name: &str,
+    location: Option<&Path>,
+) -> Result<()> {
+    let project_name = util::project_name_from_cmakelists(&project_dir.to_string_lossy())
+        .with_context(|| {
+            format!(
+                "'{}' does not look like a CMake project",
+                project_dir.display()
+            )
+        })?;
+
+    let root = match location {
+        Some(loc) => loc.to_path_buf(),
+        None => first_path_location(config)?,
+    };
+    let target = root.join(archetype_name);
+    if target.exists() {
+        return Err(anyhow!(
+            "Archetype directory '{}' already exists",
+            target.display()
+        ));
+    }
+
+    println!(
+        "🧬 Extracting archetype '{}' from project '{}'...",
+        archetype_name, project_name
+    );
+    let replacements = name_replacements(&project_name);
+    // 著作權聲明中的作者與授權改為變數；只宣告實際取代過的變數
+    let mut notices = Vec::new();
+    if let Some(author) = git_author(project_dir) {
+        notices.push(("author", "Author", author));
+    }
+    if let Some(license) = detect_license(project_dir) {
+        notices.push(("license", "License", license.to_string()));
+    }
+    let mut used = BTreeSet::new();
+    let mut count = 0;
+    for src in project_files(project_dir)? {
+        let rel = src.strip_prefix(project_dir)?;
+        let rel_template = replace_names(&rel.to_string_lossy(), &replacements);
+        let dest = target.join(rel_template);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let bytes = fs::read(&src)?;
+        match String::from_utf8(bytes) {
+            Ok(text) => {
+                // 專案中原有的 `{{` 需跳脫，避免被當成模板語法
+                let escaped = text.replace("{{", "\\{{");
+                let named = replace_names(&escaped, &replacements);
+                fs::write(&dest, replace_notices(&named, &notices, &mut used))?;
+            }
+            Err(_) => {
+                fs::copy(&src, &dest)?;
+            }
+        }
+        count += 1;
+    }
+
+    let variables: Vec<&Notice> = notices
+        .iter()
+        .filter(|(var, _, _)| used.contains(var))
+        .collect();
+    fs::write(
+        target.join("archetype.toml"),
+        archetype_toml(archetype_name, &project_name, &variables),
+    )?;
+
+    println!("✅ Extracted {} files into {}", count, target.display());
+    println!("   Review archetype.toml, then try: cproject create <name> --dry-run");
+    Ok(())
+}
+
+/// 預設放到組態中第一個本機模板位置
+fn first_path_location(config: &Config) -> Result<PathBuf> {
+    config
+        .templates
+        .locations
+        .iter()
+        .find_map(|loc| match loc {
+            TemplateLocation::Path(p) => Some(p.clone()),
+            TemplateLocation::Git(_) => None,
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "No local template location configured; \
+                 add one to [templates] locations or pass --location"
+            )
+        })
+}
+
+/// 列出專案中要納入原型的檔案：略過 `.git`、`build/`、`.cproject/` 以及被 .gitignore 忽略的檔案
+fn project_files(project_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walker = ignore::WalkBuilder::new(project_dir)
+        .hidden(false)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|e| {
+            let name = e.file_name();
+            !(name == ".git" || (e.depth() == 1 && (name == "build" || name == lock::LOCK_DIR)))
+        })
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// 專案名稱及其衍生形式對應的模板表示式，較長者優先取代
+fn name_replacements(project_name: &str) -> Vec<(String, String)> {
+    let mut replacements = vec![(project_name.to_string(), "{{name}}".to_string())];
+    let ident = render::cpp_identifier(project_name);
+    if ident != project_name {
+        replacements.push((ident, "{{cpp_identifier name}}".to_string()));
+    }
+    let upper = project_name.to_shouty_snake_case();
+    if upper != project_name && upper.len() >= 3 {
+        replacements.push((upper, "{{upper_snake name}}".to_string()));
+    }
+    replacements.sort_by_key(|r| std::cmp::Reverse(r.0.len()));
+    replacements
+}
+
+/// 只取代完整的識別字或路徑片段：前後都不能緊鄰 `[A-Za-z0-9_]`，
+/// 避免專案名稱 `core` 把 `score.cpp` 改成 `s{{name}}.cpp`
+fn replace_names(text: &str, replacements: &[(String, String)]) -> String {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    'outer: while let Some(c) = rest.chars().next() {
+        let prev_is_ident = out.chars().next_back().is_some_and(is_ident);
+        if !prev_is_ident {
+            // 依長度由長到短嘗試，較長的名稱優先
+            for (from, to) in replacements {
+                if let Some(after) = rest.strip_prefix(from.as_str()) {
+                    if !after.chars().next().is_some_and(is_ident) {
+                        out.push_str(to);
+                        rest = after;
+                        continue 'outer;
+                    }
+                }
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// 著作權聲明中可改為變數的值：（變數名稱, 提示, 由專案偵測到的預設值）
+type Notice = (&'static str, &'static str, String);
+
+/// 在著作權或授權聲明的行中（含 copyright、license 或 (c)），
+/// 將作者與授權名稱取代為對應的變數；`used` 記錄實際取代過的變數
+fn replace_notices(text: &str, notices: &[Notice], used: &mut BTreeSet<&'static str>) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let lower = line.to_lowercase();
+            if !["copyright", "license", "(c)"]
+                .iter()
+                .any(|k| lower.contains(k))
+            {
+                return line.to_string();
+            }
+            let mut line = line.to_string();
+            for (var, _, value) in notices {
+                let replaced = replace_names(&line, &[(value.clone(), format!("{{{{{}}}}}", var))]);
+                if replaced != line {
+                    used.insert(var);
+                    line = replaced;
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+fn archetype_toml(archetype_name: &str, project_name: &str, variables: &[&Notice]) -> String {
+    let mut variables_section = String::new();
+    if !variables.is_empty() {
+        variables_section.push_str("[variables]\n");
+        for (var, prompt, default) in variables {
+            variables_section.push_str(&format!(
+                "{} = {{ prompt = {:?}, default = {:?} }}\n",
+                var, prompt, default
+            ));
+        }
+        variables_section.push('\n');
+    }
+    format!(
+        r#"# {archetype_name}/archetype.toml
+# Extracted from project '{project_name}' by `cproject archetype extract`.
+
+description = "Project archetype extracted from {project_name}."
+
+{variables_section}[hooks.post_create]
+commands = ["git init", "git add .", "git commit -m 'chore: init {{{{name}}}}'"]
+"#
+    )
+}
+
+/// 以專案最後一個 commit 的作者作為 author 預設值
+fn git_author(project_dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%an"])
+        .current_dir(project_dir)
+        .output()
+        .ok()?;
+    let author = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !author.is_empty()).then_some(author)
+}
+
+/// 由 LICENSE 檔案內容推測授權
+fn detect_license(project_dir: &Path) -> Option<&'static str> {
+    let text = ["LICENSE", "LICENSE.txt", "LICENSE.md", "COPYING"]
+        .iter()
+        .find_map(|f| fs::read_to_string(project_dir.join(f)).ok())?;
+    let known = [
+        ("MIT License", "MIT"),
+        ("Apache License", "Apache-2.0"),
+        ("GNU LESSER GENERAL PUBLIC LICENSE", "LGPL-3.0"),
+        ("GNU GENERAL PUBLIC LICENSE", "GPL-3.0"),
+        ("Mozilla Public License", "MPL-2.0"),
+        ("BSD 3-Clause", "BSD-3-Clause"),
+        ("Boost Software License", "BSL-1.0"),
+    ];
+    known
+        .iter()
+        .find(|(needle, _)| text.contains(needle))
+        .map(|(_, id)| *id)
+}
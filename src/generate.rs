@@ -0,0 +1,148 @@
+// src/generate.rs
+
+use crate::archetype::Archetype;
+use crate::config::Config;
+use crate::lock::ArchetypeLock;
+use crate::render;
+use crate::util;
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 產生器模板中可直接使用、不需要宣告的變數（見 `generator_context`）
+pub(crate) const GENERATOR_VARIABLES: &[&str] = &["path", "stem", "dir", "namespaces", "namespace"];
+
+/// `cproject generate <kind> <path>`：以原型 `generators/<kind>/` 中的模板在目前專案中產生檔案
+pub fn run(
+    config: &Config,
+    kind: &str,
+    item: &str,
+    archetype_name: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    let project_dir = Path::new(".");
+    let project_name = util::project_name_from_cmakelists(".")
+        .context("Run `cproject generate` from the root of a CMake project")?;
+
+    // 優先使用建立專案時記錄的原型與變數
+    let lock = ArchetypeLock::load(project_dir).ok();
+    let archetype_name = archetype_name
+        .map(str::to_string)
+        .or_else(|| lock.as_ref().map(|l| l.archetype.clone()))
+        .unwrap_or_else(|| "app".to_string());
+    let archetype = Archetype::load(config, &archetype_name)
+        .with_context(|| format!("Failed to load archetype '{}'", archetype_name))?;
+
+    let files = archetype.generator_files(kind)?;
+    if files.is_empty() {
+        let kinds = archetype.generator_kinds();
+        return Err(anyhow!(
+            "Archetype '{}' has no '{}' generator (available: {})",
+            archetype_name,
+            kind,
+            if kinds.is_empty() {
+                "none".to_string()
+            } else {
+                kinds.join(", ")
+            }
+        ));
+    }
+
+    let context = generator_context(&archetype, lock, &project_name, item)?;
+    let hbs = render::handlebars();
+    let mut outputs = Vec::new();
+    for (rel_path, src_path) in files {
+        let rendered_rel = hbs
+            .render_template(&rel_path.to_string_lossy(), &context)
+            .with_context(|| format!("Failed to render path '{}'", rel_path.display()))?;
+        let mut dest = project_dir.join(rendered_rel);
+        let content = match String::from_utf8(fs::read(&src_path)?) {
+            Ok(template) => {
+                if src_path.extension().is_some_and(|e| e == "hbs") {
+                    dest = dest.with_extension("");
+                }
+                hbs.render_template(&template, &context)
+                    .with_context(|| format!("Failed to render '{}'", rel_path.display()))?
+                    .into_bytes()
+            }
+            // binary: just copy
+            Err(e) => e.into_bytes(),
+        };
+        outputs.push((dest, content));
+    }
+
+    // 先檢查全部的目的地，避免只產生一部分檔案
+    let existing: Vec<String> = outputs
+        .iter()
+        .filter(|(dest, _)| dest.exists())
+        .map(|(dest, _)| format!("  {}", display(dest)))
+        .collect();
+    if !existing.is_empty() && !force {
+        return Err(anyhow!(
+            "These files already exist:\n{}\nUse --force to overwrite them",
+            existing.join("\n")
+        ));
+    }
+
+    println!("🛠️ Generating {} '{}'...", kind, item);
+    for (dest, content) in &outputs {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, content)?;
+        println!("  -> {}", display(dest));
+    }
+    Ok(())
+}
+
+/// 專案變數（lock 中記錄的或原型預設值）加上產生器專用的變數：
+/// `path`（net/Socket）、`stem`（Socket）、`dir`（net）、`namespaces`（["net"]）
+/// 與 `namespace`（<專案>::net）
+pub(crate) fn generator_context(
+    archetype: &Archetype,
+    lock: Option<ArchetypeLock>,
+    project_name: &str,
+    item: &str,
+) -> Result<serde_json::Value> {
+    let path = PathBuf::from(item.trim_matches('/'));
+    let segments: Vec<String> = path
+        .components()
+        .map(|c| match c {
+            Component::Normal(s) => Ok(s.to_string_lossy().into_owned()),
+            _ => Err(anyhow!(
+                "'{}' must be a relative path without '.' or '..'",
+                item
+            )),
+        })
+        .collect::<Result<_>>()?;
+    let Some((stem, dirs)) = segments.split_last() else {
+        return Err(anyhow!("The path to generate must not be empty"));
+    };
+
+    let mut variables: serde_json::Map<String, serde_json::Value> = lock
+        .map(|l| l.variables.into_iter().collect())
+        .unwrap_or_default();
+    archetype.fill_defaults(&mut variables)?;
+
+    let namespace = std::iter::once(project_name)
+        .chain(dirs.iter().map(String::as_str))
+        .map(render::cpp_identifier)
+        .collect::<Vec<_>>()
+        .join("::");
+    variables.insert("name".to_string(), json!(project_name));
+    variables.insert(
+        "year".to_string(),
+        json!(chrono::Utc::now().format("%Y").to_string()),
+    );
+    variables.insert("path".to_string(), json!(segments.join("/")));
+    variables.insert("stem".to_string(), json!(stem));
+    variables.insert("dir".to_string(), json!(dirs.join("/")));
+    variables.insert("namespaces".to_string(), json!(dirs));
+    variables.insert("namespace".to_string(), json!(namespace));
+    Ok(serde_json::Value::Object(variables))
+}
+
+fn display(path: &Path) -> String {
+    path.strip_prefix(".").unwrap_or(path).display().to_string()
+}
@@ -0,0 +1,172 @@
+// src/git_templates.rs
+
+use crate::config::{Config, GitLocation, TemplateLocation};
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 快取根目錄：<user data dir>/cproject/templates
+fn cache_root() -> Result<PathBuf> {
+    let data = dirs::data_dir().ok_or_else(|| anyhow!("cannot resolve user data directory"))?;
+    Ok(data.join("cproject").join("templates"))
+}
+
+/// 每個 git 來源（URL + ref）對應的快取目錄。
+/// 可讀的 slug 可能相撞（`t@v1` 與 `t_v1`），因此再加上 (URL, ref) 的雜湊。
+pub fn cache_dir(loc: &GitLocation) -> Result<PathBuf> {
+    let mut key = loc.git.clone();
+    if let Some(r) = &loc.reference {
+        key.push('@');
+        key.push_str(r);
+    }
+    let slug: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut hasher = Sha256::new();
+    hasher.update(loc.git.as_bytes());
+    match &loc.reference {
+        Some(r) => {
+            hasher.update([1]);
+            hasher.update(r.as_bytes());
+        }
+        None => hasher.update([0]),
+    }
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .take(6)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(cache_root()?.join(format!("{}-{}", slug, digest)))
+}
+
+/// 確保快取存在；若尚未 clone 則 clone 並切換到指定 ref。
+/// 已存在的快取不會自動 fetch（請使用 `cproject archetype update`）。
+pub fn ensure(loc: &GitLocation) -> Result<PathBuf> {
+    let dir = cache_dir(loc)?;
+    if !dir.join(".git").exists() {
+        clone(loc, &dir)?;
+    }
+    Ok(templates_root(loc, &dir))
+}
+
+/// 更新組態中所有以 git 為來源的模板位置
+pub fn update_all(config: &Config) -> Result<()> {
+    let mut count = 0;
+    for loc in &config.templates.locations {
+        if let TemplateLocation::Git(git) = loc {
+            let rev = update(git)?;
+            println!("✅ {} @ {}", git.git, &rev[..rev.len().min(12)]);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        println!("No git template locations configured.");
+    }
+    Ok(())
+}
+
+/// 重新 fetch 遠端並切換到最新的 ref，回傳目前的 revision
+pub fn update(loc: &GitLocation) -> Result<String> {
+    let dir = cache_dir(loc)?;
+    if dir.join(".git").exists() {
+        println!("🔄 Fetching {}...", loc.git);
+        git(
+            &dir,
+            &["fetch", "--quiet", "--tags", "--force", "--prune", "origin"],
+        )
+        .with_context(|| format!("Failed to fetch {}", loc.git))?;
+        checkout(loc, &dir)?;
+    } else {
+        clone(loc, &dir)?;
+    }
+    revision(&dir)
+}
+
+/// 快取目前所在的 commit
+pub fn revision(dir: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .context("Failed to invoke git rev-parse")?;
+    if !output.status.success() {
+        return Err(anyhow!("git rev-parse HEAD failed in {}", dir.display()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn templates_root(loc: &GitLocation, dir: &Path) -> PathBuf {
+    match &loc.subdir {
+        Some(sub) => dir.join(sub),
+        None => dir.to_path_buf(),
+    }
+}
+
+fn clone(loc: &GitLocation, dir: &Path) -> Result<()> {
+    println!("📥 Cloning template repository {}...", loc.git);
+    if dir.exists() {
+        // 殘留的不完整快取
+        fs::remove_dir_all(dir)?;
+    }
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--no-checkout", &loc.git])
+        .arg(dir)
+        .status()
+        .context("Failed to invoke git clone")?;
+    if !status.success() {
+        return Err(anyhow!("git clone {} failed", loc.git));
+    }
+    checkout(loc, dir)
+}
+
+/// 將快取切換到 ref：先嘗試遠端分支，再嘗試 tag 或 commit
+fn checkout(loc: &GitLocation, dir: &Path) -> Result<()> {
+    let candidates: Vec<String> = match &loc.reference {
+        Some(r) => vec![format!("origin/{}", r), r.clone()],
+        None => vec!["origin/HEAD".to_string()],
+    };
+    for candidate in &candidates {
+        let verified = Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("{}^{{commit}}", candidate))
+            .current_dir(dir)
+            .output()
+            .context("Failed to invoke git rev-parse")?;
+        if verified.status.success() {
+            return git(
+                dir,
+                &["checkout", "--quiet", "--force", "--detach", candidate],
+            );
+        }
+    }
+    Err(anyhow!(
+        "ref '{}' not found in {}",
+        loc.reference.as_deref().unwrap_or("HEAD"),
+        loc.git
+    ))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("Failed to invoke git {}", args.join(" ")))?;
+    if !status.success() {
+        return Err(anyhow!("git {} failed", args.join(" ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,354 @@
+// src/hooks.rs
+
+use crate::render;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// archetype.toml 中的 `[hooks]` 區段
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct Hooks {
+    /// 渲染模板前，在暫存目錄中執行
+    #[serde(default)]
+    pub(crate) pre_render: HookPhase,
+    /// 渲染模板後、搬移到目的地前，在暫存目錄中執行
+    #[serde(default)]
+    pub(crate) post_render: HookPhase,
+    /// 專案搬移到目的地後執行
+    #[serde(default)]
+    pub(crate) post_create: HookPhase,
+}
+
+impl Hooks {
+    /// 依執行順序列出各階段
+    pub(crate) fn phases(&self) -> [(Phase, &[HookEntry]); 3] {
+        [
+            (Phase::PreRender, &self.pre_render.0),
+            (Phase::PostRender, &self.post_render.0),
+            (Phase::PostCreate, &self.post_create.0),
+        ]
+    }
+
+    /// 父原型的鉤子先執行，子原型的鉤子接在後面
+    pub(crate) fn merge_onto(self, parent: Hooks) -> Hooks {
+        fn chain(mut parent: HookPhase, child: HookPhase) -> HookPhase {
+            parent.0.extend(child.0);
+            parent
+        }
+        Hooks {
+            pre_render: chain(parent.pre_render, self.pre_render),
+            post_render: chain(parent.post_render, self.post_render),
+            post_create: chain(parent.post_create, self.post_create),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    PreRender,
+    PostRender,
+    PostCreate,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::PreRender => "pre_render",
+            Phase::PostRender => "post_render",
+            Phase::PostCreate => "post_create",
+        })
+    }
+}
+
+/// 單一階段的鉤子清單。
+/// 可寫成陣列，也接受舊有的 `[hooks.post_create] commands = [...]` 形式。
+#[derive(Deserialize, Debug, Default)]
+#[serde(from = "HookPhaseRepr")]
+pub(crate) struct HookPhase(pub(crate) Vec<HookEntry>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookPhaseRepr {
+    List(Vec<HookEntry>),
+    Legacy { commands: Vec<HookEntry> },
+}
+
+impl From<HookPhaseRepr> for HookPhase {
+    fn from(repr: HookPhaseRepr) -> Self {
+        match repr {
+            HookPhaseRepr::List(hooks) | HookPhaseRepr::Legacy { commands: hooks } => {
+                HookPhase(hooks)
+            }
+        }
+    }
+}
+
+/// 一個鉤子：可以只寫指令字串，或寫成帶有選項的表格
+#[derive(Deserialize, Debug, Clone)]
+#[serde(from = "HookEntryRepr")]
+pub(crate) struct HookEntry {
+    /// 要執行的指令（Handlebars 模板）
+    pub(crate) run: String,
+    /// Handlebars 條件；渲染結果為 false 時略過
+    pub(crate) when: Option<String>,
+    /// 相對於專案根目錄的工作目錄
+    pub(crate) cwd: Option<String>,
+    /// 額外的環境變數（值為 Handlebars 模板）
+    pub(crate) env: BTreeMap<String, String>,
+    /// 透過 `sh -c` 執行，而不是直接啟動程式
+    pub(crate) shell: bool,
+    /// 失敗時只顯示警告並繼續
+    pub(crate) allow_failure: bool,
+    /// 逾時秒數
+    pub(crate) timeout: Option<u64>,
+}
+
+/// 鉤子表格可用的鍵；untagged 列舉會略過其餘的鍵，由 `cproject archetype lint` 檢查
+pub(crate) const HOOK_KEYS: &[&str] = &[
+    "run",
+    "when",
+    "cwd",
+    "env",
+    "shell",
+    "allow_failure",
+    "timeout",
+];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookEntryRepr {
+    Command(String),
+    Table {
+        run: String,
+        when: Option<String>,
+        cwd: Option<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        #[serde(default)]
+        shell: bool,
+        #[serde(default)]
+        allow_failure: bool,
+        timeout: Option<u64>,
+    },
+}
+
+impl From<HookEntryRepr> for HookEntry {
+    fn from(repr: HookEntryRepr) -> Self {
+        match repr {
+            HookEntryRepr::Command(run) => HookEntry {
+                run,
+                when: None,
+                cwd: None,
+                env: BTreeMap::new(),
+                shell: false,
+                allow_failure: false,
+                timeout: None,
+            },
+            HookEntryRepr::Table {
+                run,
+                when,
+                cwd,
+                env,
+                shell,
+                allow_failure,
+                timeout,
+            } => HookEntry {
+                run,
+                when,
+                cwd,
+                env,
+                shell,
+                allow_failure,
+                timeout,
+            },
+        }
+    }
+}
+
+/// 已套用模板變數、準備執行的鉤子
+pub(crate) struct PreparedHook {
+    /// 渲染後的指令
+    pub(crate) command: String,
+    /// 非 shell 模式下拆解後的程式與參數
+    argv: Vec<String>,
+    pub(crate) shell: bool,
+    pub(crate) cwd: PathBuf,
+    /// archetype.toml 中宣告的環境變數（已渲染）
+    pub(crate) env: BTreeMap<String, String>,
+    pub(crate) allow_failure: bool,
+    pub(crate) timeout: Option<Duration>,
+    /// 不執行的原因
+    pub(crate) skip: Option<&'static str>,
+}
+
+impl PreparedHook {
+    /// 要啟動的程式（非 shell 模式）
+    pub(crate) fn program(&self) -> &str {
+        &self.argv[0]
+    }
+}
+
+/// 渲染一個階段的鉤子：套用 `when`、`cwd`、`env`，並判斷是否略過
+pub(crate) fn prepare(
+    hooks: &[HookEntry],
+    working_dir: &Path,
+    context: &serde_json::Value,
+    inside_git_repo: bool,
+) -> Result<Vec<PreparedHook>> {
+    let hbs = render::handlebars();
+    let mut prepared = Vec::new();
+    for hook in hooks {
+        let command = hbs
+            .render_template(&hook.run, context)
+            .with_context(|| format!("Failed to render hook '{}'", hook.run))?;
+        let argv: Vec<String> = shlex::Shlex::new(&command).collect();
+        if argv.is_empty() {
+            return Err(anyhow!("Empty command in hooks"));
+        }
+
+        let mut skip = None;
+        if let Some(when) = &hook.when {
+            let rendered = hbs
+                .render_template(when, context)
+                .with_context(|| format!("Failed to evaluate condition '{}'", when))?;
+            if !render::is_truthy(&rendered) {
+                skip = Some("condition is false");
+            }
+        }
+        // Inside an existing Git repository, git-related hooks are skipped
+        if skip.is_none() && inside_git_repo && argv[0] == "git" {
+            skip = Some("inside existing git repository");
+        }
+
+        let cwd = match &hook.cwd {
+            Some(dir) => working_dir.join(hbs.render_template(dir, context)?),
+            None => working_dir.to_path_buf(),
+        };
+        let mut env = BTreeMap::new();
+        for (key, value) in &hook.env {
+            env.insert(key.clone(), hbs.render_template(value, context)?);
+        }
+
+        prepared.push(PreparedHook {
+            command,
+            argv,
+            shell: hook.shell,
+            cwd,
+            env,
+            allow_failure: hook.allow_failure,
+            timeout: hook.timeout.map(Duration::from_secs),
+            skip,
+        });
+    }
+    Ok(prepared)
+}
+
+/// 執行一個階段的鉤子
+pub(crate) fn run_phase(
+    phase: Phase,
+    hooks: &[HookEntry],
+    working_dir: &Path,
+    context: &serde_json::Value,
+    inside_git_repo: bool,
+) -> Result<()> {
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    println!("🎣 Running {} hooks...", phase);
+    let vars = variable_env(context);
+    for hook in prepare(hooks, working_dir, context, inside_git_repo)? {
+        if let Some(reason) = hook.skip {
+            println!("  -> Skipping `{}` ({})", hook.command, reason);
+            continue;
+        }
+        println!("  -> Executing: `{}`", hook.command);
+
+        let mut cmd = if hook.shell {
+            let mut c = Command::new("sh");
+            c.arg("-c").arg(&hook.command);
+            c
+        } else {
+            let mut c = Command::new(&hook.argv[0]);
+            c.args(&hook.argv[1..]);
+            c
+        };
+        cmd.current_dir(&hook.cwd).envs(&vars).envs(&hook.env);
+
+        let result = cmd
+            .spawn()
+            .with_context(|| format!("Failed to execute hook command: {}", hook.command))
+            .and_then(|mut child| wait_with_timeout(&mut child, hook.timeout));
+        let failure = match result {
+            Ok(Some(status)) if status.success() => None,
+            Ok(Some(status)) => Some(anyhow!(
+                "Hook command failed ({}): {}",
+                status,
+                hook.command
+            )),
+            Ok(None) => Some(anyhow!(
+                "Hook command timed out after {}s: {}",
+                hook.timeout.unwrap_or_default().as_secs(),
+                hook.command
+            )),
+            Err(e) => Some(e),
+        };
+        if let Some(e) = failure {
+            if hook.allow_failure {
+                println!("  ⚠️ {:#} (allowed to fail, continuing)", e);
+            } else {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 模板變數以 `CPROJECT_VAR_<NAME>` 的形式匯出給鉤子
+pub(crate) fn variable_env(context: &serde_json::Value) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    if let Some(map) = context.as_object() {
+        for (key, value) in map {
+            let name: String = key
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let text = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            env.insert(format!("CPROJECT_VAR_{}", name), text);
+        }
+    }
+    env
+}
+
+/// 等待子程序結束；逾時則終止並回傳 `None`
+fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> Result<Option<ExitStatus>> {
+    let Some(limit) = timeout else {
+        return Ok(Some(child.wait()?));
+    };
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if start.elapsed() >= limit {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,513 @@
+// src/lint.rs
+
+use crate::archetype::{self, Archetype, ArchetypeConfig};
+use crate::catalog;
+use crate::config::Config;
+use crate::generate;
+use crate::hooks;
+use crate::render;
+use anyhow::{anyhow, Context, Result};
+use handlebars::template::{HelperTemplate, Parameter, Template, TemplateElement};
+use handlebars::Path as HbsPath;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 這些副檔名的檔案應為文字檔；若不是合法的 UTF-8 就會被原樣複製而不渲染
+const TEXT_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cxx", "h", "hh", "hpp", "hxx", "ipp", "inl", "cmake", "txt", "md", "json",
+    "toml", "yml", "yaml", "hbs", "in", "py", "sh",
+];
+
+/// 隱含提供、不需要在 `[variables]` 中宣告的變數
+const IMPLICIT_VARIABLES: &[&str] = &["name", "year"];
+
+/// `cproject archetype lint [name|path]`：檢查原型，有任何問題時以非零結束碼結束。
+/// 未指定時檢查所有模板位置中的原型。
+pub fn run(config: &Config, target: Option<&str>) -> Result<()> {
+    let roots = archetype::template_roots(config)?;
+    let targets: Vec<(String, PathBuf)> = match target {
+        Some(t) if Path::new(t).join("archetype.toml").is_file() => {
+            vec![(t.to_string(), PathBuf::from(t))]
+        }
+        Some(name) => {
+            let path = archetype::resolve_template_path(config, &roots, name).ok_or_else(|| {
+                anyhow!("Could not find template directory for archetype '{}'", name)
+            })?;
+            vec![(name.to_string(), path)]
+        }
+        None => {
+            let mut seen = HashSet::new();
+            roots
+                .iter()
+                .flat_map(catalog::scan)
+                .filter(|e| seen.insert(fs::canonicalize(&e.path).unwrap_or(e.path.clone())))
+                .map(|e| (e.name, e.path))
+                .collect()
+        }
+    };
+
+    let mut total = 0;
+    for (name, path) in &targets {
+        println!("🔍 Linting '{}' ({})", name, path.display());
+        let problems = match lint_archetype(config, &roots, name, path) {
+            Ok(problems) => problems,
+            Err(e) => vec![format!("{:#}", e)],
+        };
+        for problem in &problems {
+            println!("  ❌ {}", problem);
+        }
+        if problems.is_empty() {
+            println!("  ✅ No problems found");
+        }
+        total += problems.len();
+    }
+
+    if total > 0 {
+        return Err(anyhow!(
+            "Found {} problem(s) in {} archetype(s)",
+            total,
+            targets.len()
+        ));
+    }
+    Ok(())
+}
+
+fn lint_archetype(
+    config: &Config,
+    roots: &[archetype::TemplateRoot],
+    name: &str,
+    path: &Path,
+) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let archetype = Archetype::load_path(config, roots, name, path.to_path_buf())?;
+    for layer in &archetype.layers {
+        for key in unknown_keys(layer)? {
+            problems.push(format!(
+                "{}: unknown key '{}'",
+                layer.join("archetype.toml").display(),
+                key
+            ));
+        }
+    }
+
+    let declared: BTreeSet<&str> = archetype
+        .config
+        .variables
+        .keys()
+        .map(String::as_str)
+        .chain(IMPLICIT_VARIABLES.iter().copied())
+        .collect();
+    let mut context = serde_json::Map::new();
+    context.insert("name".to_string(), serde_json::json!("example"));
+    context.insert(
+        "year".to_string(),
+        serde_json::json!(chrono::Utc::now().format("%Y").to_string()),
+    );
+    archetype.fill_defaults(&mut context)?;
+    let context = serde_json::Value::Object(context);
+    // 產生器模板另外有 path、stem 等變數，以範例路徑渲染
+    let generator_context = generate::generator_context(&archetype, None, "example", "net/Socket")?;
+
+    let mut hbs = render::handlebars();
+    hbs.set_strict_mode(true);
+    let mut used = BTreeSet::new();
+    let mut check = |source: &Source, problems: &mut Vec<String>| {
+        let (what, generator, source) = (&source.what, source.generator, source.text.as_str());
+        let template = match Template::compile(source) {
+            Ok(t) => t,
+            Err(e) => {
+                problems.push(format!("{}: {}", what, e));
+                return;
+            }
+        };
+        let mut refs = References::default();
+        refs.collect(&template, false);
+        used.extend(refs.scoped.iter().cloned());
+        let mut clean = true;
+        for var in &refs.variables {
+            used.insert(var.clone());
+            let implicit = generator && generate::GENERATOR_VARIABLES.contains(&var.as_str());
+            if !declared.contains(var.as_str()) && !implicit {
+                problems.push(format!(
+                    "{}: references undeclared variable '{}'",
+                    what, var
+                ));
+                clean = false;
+            }
+        }
+        for helper in &refs.unknown_helpers {
+            problems.push(format!("{}: unknown helper '{}'", what, helper));
+            clean = false;
+        }
+        // 以預設值實際渲染一次，找出型別不符等執行期錯誤
+        if clean {
+            let context = if generator {
+                &generator_context
+            } else {
+                &context
+            };
+            if let Err(e) = hbs.render_template(source, context) {
+                problems.push(format!("{}: {}", what, e));
+            }
+        }
+    };
+
+    // 預設值只能引用先前宣告（已回答）的變數
+    for (index, (key, var)) in archetype.config.variables.iter().enumerate() {
+        for source in var.default_templates() {
+            let Ok(template) = Template::compile(source) else {
+                continue;
+            };
+            let mut refs = References::default();
+            refs.collect(&template, false);
+            for later in refs.variables.iter().filter(|v| {
+                archetype
+                    .config
+                    .variables
+                    .get_index_of(v.as_str())
+                    .is_some_and(|i| i >= index)
+            }) {
+                problems.push(format!(
+                    "default for variable '{}': references '{}', which is not declared before it",
+                    key, later
+                ));
+            }
+        }
+    }
+
+    for source in template_sources(&archetype, &mut problems)? {
+        check(&source, &mut problems);
+    }
+
+    for (phase, entries) in archetype.config.hooks.phases() {
+        let prepared = match hooks::prepare(entries, Path::new("."), &context, false) {
+            Ok(prepared) => prepared,
+            // 渲染錯誤已在上面回報
+            Err(_) => continue,
+        };
+        for hook in prepared {
+            let program = if hook.shell { "sh" } else { hook.program() };
+            // 相對路徑的程式可能由原型本身提供，無法事先檢查
+            if !program.contains('/') && which::which(program).is_err() {
+                problems.push(format!(
+                    "{} hook `{}`: program '{}' not found on PATH",
+                    phase, hook.command, program
+                ));
+            }
+        }
+    }
+
+    // 只檢查本層宣告的變數；基底層的變數可能只在繼承它的原型中使用
+    let own = ArchetypeConfig::read(path)?;
+    let mut unused: Vec<&String> = own
+        .variables
+        .keys()
+        .filter(|var| !used.contains(var.as_str()))
+        .collect();
+    if !unused.is_empty() {
+        let used_by_children = used_by_descendants(config, roots, path)?;
+        unused.retain(|var| !used_by_children.contains(var.as_str()));
+    }
+    for var in unused {
+        problems.push(format!("variable '{}' is declared but never used", var));
+    }
+    Ok(problems)
+}
+
+/// 原型中一段以 Handlebars 處理的字串
+struct Source {
+    /// 問題訊息中顯示的位置
+    what: String,
+    text: String,
+    /// 位於 `generators/<kind>/` 中，可使用產生器提供的變數
+    generator: bool,
+}
+
+impl Source {
+    fn new(what: String, text: String) -> Self {
+        Source {
+            what,
+            text,
+            generator: false,
+        }
+    }
+}
+
+/// 原型中所有以 Handlebars 處理的字串：變數預設值、路徑、檔案內容、產生器、
+/// [files] 條件與鉤子。不會被渲染的非 UTF-8 文字檔會記錄到 `problems`。
+fn template_sources(archetype: &Archetype, problems: &mut Vec<String>) -> Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    for (key, var) in &archetype.config.variables {
+        for source in var.default_templates() {
+            sources.push(Source::new(
+                format!("default for variable '{}'", key),
+                source.to_string(),
+            ));
+        }
+    }
+
+    let mut files = Vec::new();
+    for (rel, src) in archetype.template_files()? {
+        let raw = archetype.is_raw(&rel)?;
+        files.push((PathBuf::new(), rel, src, raw, false));
+    }
+    for kind in archetype.generator_kinds() {
+        let prefix = Path::new(archetype::GENERATORS_DIR).join(&kind);
+        for (rel, src) in archetype.generator_files(&kind)? {
+            files.push((prefix.clone(), rel, src, false, true));
+        }
+    }
+    for (prefix, rel, src, raw, generator) in files {
+        let display = prefix.join(&rel).to_string_lossy().to_string();
+        sources.push(Source {
+            what: format!("path '{}'", display),
+            text: rel.to_string_lossy().to_string(),
+            generator,
+        });
+        if raw {
+            continue;
+        }
+        let bytes = fs::read(&src).with_context(|| format!("Failed to read {}", src.display()))?;
+        match String::from_utf8(bytes) {
+            Ok(text) => sources.push(Source {
+                what: display,
+                text,
+                generator,
+            }),
+            Err(e) => {
+                if let Some(reason) = accidental_binary(&src, e.as_bytes()) {
+                    problems.push(format!(
+                        "{}: not valid UTF-8 and would be copied without rendering ({})",
+                        display, reason
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut rules: Vec<_> = archetype.config.files.iter().collect();
+    rules.sort();
+    for (glob, condition) in rules {
+        sources.push(Source::new(
+            format!("[files] condition for '{}'", glob),
+            condition.clone(),
+        ));
+    }
+
+    for (phase, entries) in archetype.config.hooks.phases() {
+        for hook in entries {
+            let what = format!("{} hook `{}`", phase, hook.run);
+            for source in std::iter::once(&hook.run)
+                .chain(&hook.when)
+                .chain(&hook.cwd)
+                .chain(hook.env.values())
+            {
+                sources.push(Source::new(what.clone(), source.clone()));
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// 所有模板位置中、繼承鏈包含 `layer` 的其他原型所引用的變數
+fn used_by_descendants(
+    config: &Config,
+    roots: &[archetype::TemplateRoot],
+    layer: &Path,
+) -> Result<BTreeSet<String>> {
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let layer = canonical(layer);
+    let mut seen = HashSet::new();
+    let mut used = BTreeSet::new();
+    for entry in roots.iter().flat_map(catalog::scan) {
+        if canonical(&entry.path) == layer || !seen.insert(canonical(&entry.path)) {
+            continue;
+        }
+        // 無法載入的原型會在檢查它本身時回報
+        let Ok(child) = Archetype::load_path(config, roots, &entry.name, entry.path.clone()) else {
+            continue;
+        };
+        if !child.layers.iter().any(|l| canonical(l) == layer) {
+            continue;
+        }
+        for source in template_sources(&child, &mut Vec::new())? {
+            if let Ok(template) = Template::compile(&source.text) {
+                let mut refs = References::default();
+                refs.collect(&template, false);
+                used.extend(refs.variables);
+                used.extend(refs.scoped);
+            }
+        }
+    }
+    Ok(used)
+}
+
+/// 以 `serde_ignored` 解析 archetype.toml，列出不認得的鍵
+fn unknown_keys(template_path: &Path) -> Result<Vec<String>> {
+    let path = template_path.join("archetype.toml");
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut unknown = Vec::new();
+    let _: ArchetypeConfig = serde_ignored::deserialize(toml::Deserializer::new(&content), |key| {
+        unknown.push(key.to_string())
+    })
+    .with_context(|| format!("Failed to parse {}", path.display()))?;
+    // 鉤子以 untagged 列舉解析，serde_ignored 看不到其中多餘的鍵
+    if let Ok(toml::Value::Table(root)) = content.parse::<toml::Value>() {
+        if let Some(toml::Value::Table(phases)) = root.get("hooks") {
+            let known: Vec<String> = hooks::Hooks::default()
+                .phases()
+                .iter()
+                .map(|(phase, _)| phase.to_string())
+                .collect();
+            // 不認得的階段已由 serde_ignored 回報
+            for (phase, value) in phases.iter().filter(|(phase, _)| known.contains(phase)) {
+                let prefix = format!("hooks.{}", phase);
+                match value {
+                    toml::Value::Array(entries) => {
+                        unknown_hook_keys(&prefix, entries, &mut unknown)
+                    }
+                    toml::Value::Table(legacy) => {
+                        for (key, value) in legacy {
+                            match (key.as_str(), value) {
+                                ("commands", toml::Value::Array(entries)) => unknown_hook_keys(
+                                    &format!("{}.commands", prefix),
+                                    entries,
+                                    &mut unknown,
+                                ),
+                                ("commands", _) => {}
+                                _ => unknown.push(format!("{}.{}", prefix, key)),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(unknown)
+}
+
+fn unknown_hook_keys(prefix: &str, entries: &[toml::Value], unknown: &mut Vec<String>) {
+    for (index, entry) in entries.iter().enumerate() {
+        let toml::Value::Table(table) = entry else {
+            continue;
+        };
+        for key in table.keys() {
+            if !hooks::HOOK_KEYS.contains(&key.as_str()) {
+                unknown.push(format!("{}[{}].{}", prefix, index, key));
+            }
+        }
+    }
+}
+
+/// 判斷非 UTF-8 的檔案是否其實應該是文字檔
+fn accidental_binary(path: &Path, bytes: &[u8]) -> Option<&'static str> {
+    if bytes.windows(2).any(|w| w == b"{{") {
+        return Some("contains template expressions");
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if TEXT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+        || path.file_name().is_some_and(|n| n == "CMakeLists.txt")
+    {
+        return Some("text file extension");
+    }
+    if !bytes.iter().take(8192).any(|b| *b == 0) {
+        return Some("looks like text in another encoding");
+    }
+    None
+}
+
+/// 模板中引用到的變數與 helper
+#[derive(Default)]
+struct References {
+    /// 最外層（根 context）引用的變數
+    variables: BTreeSet<String>,
+    /// `#each` / `#with` 區塊內引用的名稱；context 已改變，只當作「有使用」
+    scoped: BTreeSet<String>,
+    unknown_helpers: BTreeSet<String>,
+}
+
+impl References {
+    fn collect(&mut self, template: &Template, scoped: bool) {
+        for element in &template.elements {
+            match element {
+                TemplateElement::Expression(h)
+                | TemplateElement::HtmlExpression(h)
+                | TemplateElement::HelperBlock(h) => self.helper(h, scoped),
+                TemplateElement::DecoratorExpression(d)
+                | TemplateElement::DecoratorBlock(d)
+                | TemplateElement::PartialExpression(d)
+                | TemplateElement::PartialBlock(d) => {
+                    for param in d.params.iter().chain(d.hash.values()) {
+                        self.parameter(param, scoped);
+                    }
+                    if let Some(t) = &d.template {
+                        self.collect(t, scoped);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn helper(&mut self, h: &HelperTemplate, scoped: bool) {
+        let name_only = !h.block && h.params.is_empty() && h.hash.is_empty();
+        match &h.name {
+            Parameter::Name(name) if name_only && !render::HELPERS.contains(&name.as_str()) => {
+                self.variable(name, scoped)
+            }
+            Parameter::Name(name) => {
+                if !render::HELPERS.contains(&name.as_str()) {
+                    self.unknown_helpers.insert(name.clone());
+                }
+            }
+            // 沒有參數的 `{{foo}}` 解析為路徑；名稱與 helper 相同時呼叫的是 helper
+            Parameter::Path(HbsPath::Relative((_, raw)))
+                if render::HELPERS.contains(&raw.as_str()) => {}
+            other => self.parameter(other, scoped),
+        }
+        for param in h.params.iter().chain(h.hash.values()) {
+            self.parameter(param, scoped);
+        }
+        let inner_scoped = scoped
+            || matches!(&h.name, Parameter::Name(n) if n == "each" || n == "with")
+            || h.block_param.is_some();
+        if let Some(t) = &h.template {
+            self.collect(t, inner_scoped);
+        }
+        if let Some(t) = &h.inverse {
+            self.collect(t, scoped);
+        }
+    }
+
+    fn parameter(&mut self, param: &Parameter, scoped: bool) {
+        match param {
+            Parameter::Name(name) => self.variable(name, scoped),
+            Parameter::Path(HbsPath::Relative((_, raw))) => self.variable(raw, scoped),
+            Parameter::Subexpression(sub) => match sub.element.as_ref() {
+                TemplateElement::Expression(h) | TemplateElement::HelperBlock(h) => {
+                    self.helper(h, scoped)
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// 只記錄路徑的第一段（`a.b` -> `a`）；`this` 與 `@` 開頭的不算，`../a` 視為區塊內的引用
+    fn variable(&mut self, raw: &str, scoped: bool) {
+        let parent = raw.trim_start_matches("../");
+        let scoped = scoped || parent.len() != raw.len();
+        let root = parent.split(['.', '/']).next().unwrap_or_default();
+        if root.is_empty() || root == "this" || root.starts_with('@') {
+            return;
+        }
+        if scoped {
+            self.scoped.insert(root.to_string());
+        } else {
+            self.variables.insert(root.to_string());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// src/lock.rs
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 專案內記錄原型資訊的目錄
+pub const LOCK_DIR: &str = ".cproject";
+const LOCK_FILE: &str = "archetype.lock";
+/// 建立（或上次升級）時渲染結果的副本，作為三方合併的共同祖先
+const BASE_DIR: &str = "base";
+
+/// `.cproject/archetype.lock`：建立專案時使用的原型、變數與每個檔案的雜湊
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchetypeLock {
+    /// 建立時指定的原型名稱
+    pub archetype: String,
+    /// 原型所在的模板目錄
+    pub source: PathBuf,
+    /// 模板目錄所在 git 儲存庫的 commit（若有）
+    pub revision: Option<String>,
+    /// 建立時目的地是否位於既有的 git 儲存庫中（影響 .gitignore 是否產生）
+    #[serde(default)]
+    pub inside_git_repo: bool,
+    /// 變數的回答
+    #[serde(default)]
+    pub variables: BTreeMap<String, serde_json::Value>,
+    /// 相對路徑 -> 原型渲染結果的 SHA-256
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+impl ArchetypeLock {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(LOCK_DIR).join(LOCK_FILE)
+    }
+
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let path = Self::path(project_dir);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        let path = Self::path(project_dir);
+        fs::create_dir_all(path.parent().unwrap())?;
+        let body = toml::to_string_pretty(self).context("Failed to serialize archetype.lock")?;
+        fs::write(
+            &path,
+            format!(
+                "# Generated by cproject; used by `cproject upgrade`. Do not edit.\n\n{}",
+                body
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// 記錄一個渲染後的檔案：更新雜湊並保存共同祖先副本
+    pub fn record_file(&mut self, project_dir: &Path, rel: &str, content: &[u8]) -> Result<()> {
+        self.files.insert(rel.to_string(), hash(content));
+        let base = base_path(project_dir, rel);
+        if let Some(parent) = base.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(base, content)?;
+        Ok(())
+    }
+}
+
+/// 共同祖先副本的路徑
+pub fn base_path(project_dir: &Path, rel: &str) -> PathBuf {
+    project_dir.join(LOCK_DIR).join(BASE_DIR).join(rel)
+}
+
+pub fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// lock 檔中統一使用 `/` 分隔的相對路徑
+pub fn rel_key(rel: &Path) -> String {
+    rel.to_string_lossy().replace('\\', "/")
+}
@@ -1,16 +1,29 @@
 // src/main.rs
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing_subscriber::EnvFilter;
 
 // 載入我們新的核心模組
 mod archetype;
 mod build;
+mod catalog;
 mod config;
 mod doctor;
+mod embedded;
+mod extract;
+mod generate;
+mod git_templates;
+mod hooks;
+mod lint;
+mod lock;
+mod manifest;
 mod pkg;
+mod presets;
+mod render;
+mod sanitize;
+mod upgrade;
 mod util;
 
 #[derive(Parser)]
@@ -35,31 +48,184 @@ enum Cmd {
         /// The name of the new project directory.
         project_name: String,
         /// Generate a library project instead of an executable
-        #[arg(long, short = 'l')]
+        #[arg(long, short = 'l', conflicts_with = "archetype")]
         lib: bool,
+        /// The archetype to use: app, lib, shared, header-only, c, or any name/path
+        #[arg(long)]
+        archetype: Option<String>,
         /// Use defaults for all prompts (non-interactive)
         #[arg(long, alias = "yes")]
         defaults: bool,
+        /// Print the files, variables and hooks without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Set a template variable (repeatable): --var key=value
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Read template variables from a TOML or JSON file
+        #[arg(long, value_name = "FILE")]
+        vars_file: Option<PathBuf>,
+        /// Keep the project directory if a post-create hook fails
+        #[arg(long)]
+        keep_on_failure: bool,
+    },
+    /// Initialize a project in the current directory, named after the directory.
+    Init {
+        /// Generate a library project instead of an executable
+        #[arg(long, short = 'l', conflicts_with = "archetype")]
+        lib: bool,
+        /// The archetype to use: app, lib, shared, header-only, c, or any name/path
+        #[arg(long)]
+        archetype: Option<String>,
+        /// Keep files that already exist and only add missing ones
+        #[arg(long, conflicts_with = "force")]
+        merge: bool,
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+        /// Use defaults for all prompts (non-interactive)
+        #[arg(long, alias = "yes")]
+        defaults: bool,
+        /// Print the files, variables and hooks without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Set a template variable (repeatable): --var key=value
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Read template variables from a TOML or JSON file
+        #[arg(long, value_name = "FILE")]
+        vars_file: Option<PathBuf>,
+    },
+    /// Generate files in the current project from an archetype generator (class, test, module).
+    Generate {
+        /// Generator name, e.g. class, test or module
+        kind: String,
+        /// Path of the item relative to the source roots, e.g. net/Socket
+        path: String,
+        /// Archetype providing the generator (defaults to the one recorded at creation)
+        #[arg(long)]
+        archetype: Option<String>,
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
     },
     /// Configure & build the project.
     Build {
-        #[arg(long)]
+        /// Shorthand for --profile debug
+        #[arg(long, conflicts_with = "profile")]
         debug: bool,
+        /// Build profile from cproject.toml (built-in: debug, release)
+        #[arg(long)]
+        profile: Option<String>,
+        /// CMake generator, e.g. Ninja or "Unix Makefiles" (default: Ninja when installed)
+        #[arg(long)]
+        generator: Option<String>,
+        /// Run the CMake configure step even if its inputs are unchanged
+        #[arg(long)]
+        reconfigure: bool,
+        /// Build with sanitizers (address, undefined, thread, memory) in a separate build directory
+        #[arg(long, value_delimiter = ',', value_name = "LIST")]
+        sanitize: Vec<sanitize::Sanitizer>,
+        /// Configure and build through a build preset from CMakePresets.json
+        #[arg(long, conflicts_with_all = ["debug", "profile", "generator", "reconfigure", "sanitize"])]
+        preset: Option<String>,
     },
     /// Build & run the executable.
     Run {
-        #[arg(long)]
+        /// Shorthand for --profile debug
+        #[arg(long, conflicts_with = "profile")]
         debug: bool,
+        /// Build profile from cproject.toml (built-in: debug, release)
+        #[arg(long)]
+        profile: Option<String>,
+        /// CMake generator, e.g. Ninja or "Unix Makefiles" (default: Ninja when installed)
+        #[arg(long)]
+        generator: Option<String>,
+        /// Run the CMake configure step even if its inputs are unchanged
+        #[arg(long)]
+        reconfigure: bool,
+        /// Build with sanitizers (address, undefined, thread, memory) in a separate build directory
+        #[arg(long, value_delimiter = ',', value_name = "LIST")]
+        sanitize: Vec<sanitize::Sanitizer>,
     },
     /// Build & run tests.
-    Test,
+    Test {
+        /// Build profile from cproject.toml (defaults to debug)
+        #[arg(long)]
+        profile: Option<String>,
+        /// CMake generator, e.g. Ninja or "Unix Makefiles" (default: Ninja when installed)
+        #[arg(long)]
+        generator: Option<String>,
+        /// Run the CMake configure step even if its inputs are unchanged
+        #[arg(long)]
+        reconfigure: bool,
+        /// Build with sanitizers (address, undefined, thread, memory) in a separate build directory
+        #[arg(long, value_delimiter = ',', value_name = "LIST")]
+        sanitize: Vec<sanitize::Sanitizer>,
+    },
+    /// Inspect the project's CMake presets.
+    Presets {
+        #[command(subcommand)]
+        sub: PresetsCmd,
+    },
     /// Manage vcpkg packages (experimental).
     Pkg {
         #[command(subcommand)]
         sub: PkgCmd,
     },
+    /// Re-apply the project's archetype, merging template changes into it.
+    Upgrade {
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Check environment and tools.
     Doctor,
+    /// Manage project archetypes.
+    Archetype {
+        #[command(subcommand)]
+        sub: ArchetypeCmd,
+    },
+}
+
+#[derive(Subcommand)]
+enum ArchetypeCmd {
+    /// Fetch git-backed template locations and refresh the local cache.
+    Update,
+    /// List archetypes found in every template location.
+    List,
+    /// Show an archetype's variables and hooks.
+    Show { name: String },
+    /// Explain how an archetype name is resolved.
+    Which { name: String },
+    /// Turn an existing project into a new archetype.
+    Extract {
+        /// The project directory to extract from.
+        project_dir: PathBuf,
+        /// Name (relative path) of the new archetype.
+        archetype_name: String,
+        /// Template location to write into (defaults to the first configured path).
+        #[arg(long)]
+        location: Option<PathBuf>,
+    },
+    /// Copy a built-in archetype into a directory for customization.
+    Export {
+        /// Built-in archetype name or alias (e.g. app, lib, default/base).
+        name: String,
+        /// Directory to write the archetype into.
+        dir: PathBuf,
+    },
+    /// Validate archetypes; exits non-zero if any problem is found.
+    Lint {
+        /// Archetype name or directory (defaults to every archetype in every location).
+        target: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PresetsCmd {
+    /// List presets from CMakePresets.json and CMakeUserPresets.json.
+    List,
 }
 
 #[derive(Subcommand)]
@@ -101,45 +267,174 @@ fn main() -> Result<()> {
         Cmd::Create {
             project_name,
             lib,
+            archetype,
             defaults,
+            dry_run,
+            vars,
+            vars_file,
+            keep_on_failure,
         } => {
-            let archetype_name = if lib { "lib" } else { "app" };
+            let archetype_name =
+                archetype.unwrap_or_else(|| if lib { "lib" } else { "app" }.to_string());
             // 1. 載入原型
-            let archetype = archetype::Archetype::load(&config, archetype_name)
+            let archetype = archetype::Archetype::load(&config, &archetype_name)
                 .with_context(|| format!("Failed to load archetype '{}'", archetype_name))?;
 
             // 2. 實例化原型
             let dest_path = PathBuf::from(&project_name);
+            let options = archetype::InstantiateOptions {
+                use_defaults: defaults,
+                dry_run,
+                vars: archetype::parse_provided_vars(&vars, vars_file.as_deref())?,
+                keep_on_failure,
+                existing: None,
+            };
             archetype
-                .instantiate(&project_name, &dest_path, defaults)
+                .instantiate(&project_name, &dest_path, &options)
                 .with_context(|| format!("Failed to instantiate project '{}'", project_name))?;
+            if dry_run {
+                return Ok(());
+            }
 
             // Post-create: ensure vcpkg is installed and ready
-            let setup_path = config
-                .vcpkg_root
-                .as_ref()
-                .and_then(|p| p.to_str())
-                .map(|s| s.to_string());
-            pkg::vcpkg_setup(setup_path.as_deref())?;
+            setup_vcpkg(&config)?;
+        }
+        Cmd::Init {
+            lib,
+            archetype,
+            merge,
+            force,
+            defaults,
+            dry_run,
+            vars,
+            vars_file,
+        } => {
+            let archetype_name =
+                archetype.unwrap_or_else(|| if lib { "lib" } else { "app" }.to_string());
+            let archetype = archetype::Archetype::load(&config, &archetype_name)
+                .with_context(|| format!("Failed to load archetype '{}'", archetype_name))?;
+
+            let cwd = std::env::current_dir()?;
+            let project_name = cwd
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("Cannot derive a project name from '{}'", cwd.display()))?;
+            let existing = if force {
+                archetype::ExistingFiles::Force
+            } else if merge {
+                archetype::ExistingFiles::Merge
+            } else {
+                archetype::ExistingFiles::Refuse
+            };
+            let options = archetype::InstantiateOptions {
+                use_defaults: defaults,
+                dry_run,
+                vars: archetype::parse_provided_vars(&vars, vars_file.as_deref())?,
+                keep_on_failure: false,
+                existing: Some(existing),
+            };
+            archetype
+                .instantiate(&project_name, Path::new("."), &options)
+                .with_context(|| format!("Failed to initialize project '{}'", project_name))?;
+            if dry_run {
+                return Ok(());
+            }
+            setup_vcpkg(&config)?;
+        }
+        Cmd::Generate {
+            kind,
+            path,
+            archetype,
+            force,
+        } => generate::run(&config, &kind, &path, archetype.as_deref(), force)?,
+        Cmd::Build {
+            preset: Some(preset),
+            ..
+        } => presets::build(&config, &preset)?,
+        Cmd::Build {
+            debug,
+            profile,
+            generator,
+            reconfigure,
+            sanitize,
+            ..
+        } => {
+            let profile = build_profile(profile.as_deref(), debug, "release", generator, sanitize)?;
+            build::cmake_build(&config, &profile, false, reconfigure)?;
         }
-        Cmd::Build { debug } => build::cmake_build(&config, debug, false)?,
-        Cmd::Run { debug } => {
-            build::cmake_build(&config, debug, false)?;
-            build::run_exe(&config, debug)?;
+        Cmd::Run {
+            debug,
+            profile,
+            generator,
+            reconfigure,
+            sanitize,
+        } => {
+            let profile = build_profile(profile.as_deref(), debug, "release", generator, sanitize)?;
+            build::cmake_build(&config, &profile, false, reconfigure)?;
+            build::run_exe(&config, &profile)?;
         }
-        Cmd::Test => {
+        Cmd::Test {
+            profile,
+            generator,
+            reconfigure,
+            sanitize,
+        } => {
             // 測試通常在 debug 模式下進行
-            build::cmake_build(&config, true, true)?;
-            build::run_tests(&config)?;
+            let profile = build_profile(profile.as_deref(), true, "debug", generator, sanitize)?;
+            build::cmake_build(&config, &profile, true, reconfigure)?;
+            build::run_tests(&config, &profile)?;
         }
+        Cmd::Presets { sub } => match sub {
+            PresetsCmd::List => presets::list()?,
+        },
         Cmd::Pkg { sub } => match sub {
             PkgCmd::Add { name } => pkg::add(&name)?,
             PkgCmd::Rm { name } => pkg::rm(&name)?,
             PkgCmd::Search { name } => pkg::search(&name)?,
             PkgCmd::Setup { path } => pkg::vcpkg_setup(path.as_deref())?,
         },
+        Cmd::Upgrade { dry_run } => upgrade::run(&config, dry_run)?,
         Cmd::Doctor => doctor::run()?,
+        Cmd::Archetype { sub } => match sub {
+            ArchetypeCmd::Update => git_templates::update_all(&config)?,
+            ArchetypeCmd::List => catalog::list(&config)?,
+            ArchetypeCmd::Show { name } => catalog::show(&config, &name)?,
+            ArchetypeCmd::Which { name } => catalog::which(&config, &name)?,
+            ArchetypeCmd::Extract {
+                project_dir,
+                archetype_name,
+                location,
+            } => extract::run(&config, &project_dir, &archetype_name, location.as_deref())?,
+            ArchetypeCmd::Export { name, dir } => embedded::export(&config, &name, &dir)?,
+            ArchetypeCmd::Lint { target } => lint::run(&config, target.as_deref())?,
+        },
     }
 
     Ok(())
 }
+
+/// 解析 build / run / test 使用的設定檔並套用命令列選項；
+/// 使用 sanitizer 且未指定設定檔時以 debug 建置
+fn build_profile(
+    name: Option<&str>,
+    debug: bool,
+    default: &str,
+    generator: Option<String>,
+    sanitizers: Vec<sanitize::Sanitizer>,
+) -> Result<manifest::Profile> {
+    let debug = debug || !sanitizers.is_empty();
+    let mut profile = manifest::resolve_profile(name, debug, default)?;
+    profile.generator = generator.or(profile.generator);
+    sanitize::apply(&mut profile, sanitizers)?;
+    Ok(profile)
+}
+
+/// 建立專案後確保 vcpkg 已安裝並可使用
+fn setup_vcpkg(config: &config::Config) -> Result<()> {
+    let setup_path = config
+        .vcpkg_root
+        .as_ref()
+        .and_then(|p| p.to_str())
+        .map(|s| s.to_string());
+    pkg::vcpkg_setup(setup_path.as_deref())
+}
@@ -0,0 +1,153 @@
+// src/manifest.rs
+
+use crate::sanitize::Sanitizer;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 專案根目錄中的專案層級設定檔（與使用者層級的 .cproject.toml 不同）
+pub const MANIFEST_FILE: &str = "cproject.toml";
+
+/// 內建的建置設定檔；manifest 中同名的 `[profile.*]` 會疊加在其上
+const BUILTIN_PROFILES: &[(&str, &str)] = &[("debug", "Debug"), ("release", "Release")];
+
+/// cproject.toml
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    profile: BTreeMap<String, ProfileDef>,
+}
+
+/// `[profile.<name>]`：未設定的欄位沿 `inherits` 由父設定檔取得
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ProfileDef {
+    build_type: Option<String>,
+    #[serde(default)]
+    defines: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    cxx_flags: Vec<String>,
+    generator: Option<String>,
+    build_dir: Option<PathBuf>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    inherits: Option<String>,
+}
+
+/// 解析完成、可直接交給 CMake 的建置設定檔
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub build_type: String,
+    /// `-D<KEY>=<VALUE>`；布林值轉為 ON / OFF
+    pub defines: BTreeMap<String, String>,
+    pub cxx_flags: Vec<String>,
+    pub generator: Option<String>,
+    pub build_dir: PathBuf,
+    /// 執行 CMake 與建置出的程式時加入的環境變數
+    pub env: BTreeMap<String, String>,
+    /// 由 `--sanitize` 套用的 sanitizer
+    pub sanitizers: Vec<Sanitizer>,
+}
+
+impl Manifest {
+    /// 讀取 `<project_dir>/cproject.toml`；不存在時只有內建設定檔
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let path = project_dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(Manifest::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// 依名稱解析設定檔。自訂設定檔未指定 `inherits` 時繼承 `debug`；
+    /// `build-dir` 不繼承，預設為 `build/<name>`。
+    pub fn profile(&self, name: &str) -> Result<Profile> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut current = name.to_string();
+        loop {
+            if chain.contains(&current) {
+                chain.push(current);
+                return Err(anyhow!("Profile inheritance cycle: {}", chain.join(" -> ")));
+            }
+            let def = self.profile.get(&current);
+            let builtin = BUILTIN_PROFILES.iter().any(|(n, _)| *n == current);
+            if def.is_none() && !builtin {
+                let mut known: Vec<&str> = BUILTIN_PROFILES.iter().map(|(n, _)| *n).collect();
+                known.extend(self.profile.keys().map(String::as_str));
+                known.sort();
+                known.dedup();
+                return Err(anyhow!(
+                    "Unknown profile '{}' (available: {})",
+                    current,
+                    known.join(", ")
+                ));
+            }
+            chain.push(current.clone());
+            match def.and_then(|d| d.inherits.clone()) {
+                Some(parent) => current = parent,
+                None if builtin => break,
+                None => current = "debug".to_string(),
+            }
+        }
+
+        // 由最上層的祖先往下套用
+        let root = chain.last().expect("chain is never empty");
+        let mut profile = Profile {
+            name: name.to_string(),
+            build_type: BUILTIN_PROFILES
+                .iter()
+                .find(|(n, _)| n == root)
+                .map(|(_, t)| t.to_string())
+                .unwrap_or_default(),
+            defines: BTreeMap::new(),
+            cxx_flags: Vec::new(),
+            generator: None,
+            build_dir: Path::new("build").join(name),
+            env: BTreeMap::new(),
+            sanitizers: Vec::new(),
+        };
+        for (i, link) in chain.iter().enumerate().rev() {
+            let Some(def) = self.profile.get(link) else {
+                continue;
+            };
+            if let Some(build_type) = &def.build_type {
+                profile.build_type = build_type.clone();
+            }
+            for (key, value) in &def.defines {
+                profile.defines.insert(key.clone(), define_value(value));
+            }
+            profile.cxx_flags.extend(def.cxx_flags.iter().cloned());
+            if def.generator.is_some() {
+                profile.generator = def.generator.clone();
+            }
+            if i == 0 {
+                if let Some(dir) = &def.build_dir {
+                    profile.build_dir = dir.clone();
+                }
+            }
+            profile.env.extend(def.env.clone());
+        }
+        Ok(profile)
+    }
+}
+
+/// `--profile` 未指定時，`--debug` 代表內建的 `debug` 設定檔，否則為 `default`
+pub fn resolve_profile(name: Option<&str>, debug: bool, default: &str) -> Result<Profile> {
+    let name = name.unwrap_or(if debug { "debug" } else { default });
+    Manifest::load(Path::new("."))?.profile(name)
+}
+
+fn define_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Boolean(true) => "ON".to_string(),
+        toml::Value::Boolean(false) => "OFF".to_string(),
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,212 @@
+// src/presets.rs
+
+use crate::build;
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// CMake 讀取的 preset 檔案；CMakeUserPresets.json 為個人設定，不應加入版本控制
+const PRESET_FILES: &[&str] = &["CMakePresets.json", "CMakeUserPresets.json"];
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct PresetsFile {
+    #[serde(default)]
+    configure_presets: Vec<Preset>,
+    #[serde(default)]
+    build_presets: Vec<Preset>,
+    #[serde(default)]
+    test_presets: Vec<Preset>,
+}
+
+/// 只讀取 cproject 需要的欄位，其餘交給 CMake 處理
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Preset {
+    name: String,
+    display_name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    hidden: bool,
+    configure_preset: Option<String>,
+    #[serde(default)]
+    inherits: Inherits,
+}
+
+/// `inherits` 可以是字串或字串陣列
+#[derive(Deserialize, Debug, Default)]
+#[serde(untagged)]
+enum Inherits {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Inherits {
+    fn names(&self) -> &[String] {
+        match self {
+            Inherits::None => &[],
+            Inherits::One(name) => std::slice::from_ref(name),
+            Inherits::Many(names) => names,
+        }
+    }
+}
+
+/// 取出 preset 檔案中的某一區段
+type Section = fn(&PresetsFile) -> &Vec<Preset>;
+
+const CONFIGURE: Section = |f| &f.configure_presets;
+const BUILD: Section = |f| &f.build_presets;
+const TEST: Section = |f| &f.test_presets;
+
+/// 目前目錄中所有 preset 檔案，依 CMake 的讀取順序
+struct Presets {
+    files: Vec<(&'static str, PresetsFile)>,
+}
+
+impl Presets {
+    fn load(project_dir: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        for name in PRESET_FILES {
+            let path = project_dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let file: PresetsFile = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            files.push((*name, file));
+        }
+        if files.is_empty() {
+            return Err(anyhow!(
+                "No {} found in the current directory",
+                PRESET_FILES.join(" or ")
+            ));
+        }
+        Ok(Presets { files })
+    }
+
+    fn section(&self, section: Section) -> impl Iterator<Item = &Preset> {
+        self.files.iter().flat_map(move |(_, f)| section(f))
+    }
+
+    /// build / test preset 使用的 configure preset；未直接指定時沿同一區段的 `inherits` 尋找
+    fn configure_preset_of<'a>(&'a self, preset: &'a Preset, section: Section) -> Option<&'a str> {
+        self.inherited_configure_preset(preset, section, 0)
+    }
+
+    fn inherited_configure_preset<'a>(
+        &'a self,
+        preset: &'a Preset,
+        section: Section,
+        depth: usize,
+    ) -> Option<&'a str> {
+        if let Some(configure) = &preset.configure_preset {
+            return Some(configure);
+        }
+        // 循環繼承是不合法的 preset，交給 CMake 回報
+        if depth > 32 {
+            return None;
+        }
+        preset.inherits.names().iter().find_map(|parent| {
+            self.section(section)
+                .find(|p| &p.name == parent)
+                .and_then(|p| self.inherited_configure_preset(p, section, depth + 1))
+        })
+    }
+}
+
+/// `cproject presets list`：列出 CMakePresets.json 與 CMakeUserPresets.json 中可用的 preset
+pub fn list() -> Result<()> {
+    let presets = Presets::load(Path::new("."))?;
+    let sections = [
+        ("Configure presets", CONFIGURE),
+        ("Build presets", BUILD),
+        ("Test presets", TEST),
+    ];
+    for (title, section) in sections {
+        println!("{}:", title);
+        let mut any = false;
+        for (file, contents) in &presets.files {
+            for preset in section(contents).iter().filter(|p| !p.hidden) {
+                any = true;
+                let mut line = format!("  {}", preset.name);
+                if let Some(label) = preset.display_name.as_ref().or(preset.description.as_ref()) {
+                    line.push_str(&format!(" — {}", label));
+                }
+                if let Some(configure) = presets.configure_preset_of(preset, section) {
+                    line.push_str(&format!(" (configure: {})", configure));
+                }
+                println!("{}  [{}]", line, file);
+            }
+        }
+        if !any {
+            println!("  (none)");
+        }
+    }
+    Ok(())
+}
+
+/// `cproject build --preset <name>`：以 CMake preset 設定並建置
+pub fn build(config: &Config, name: &str) -> Result<()> {
+    let presets = Presets::load(Path::new("."))?;
+    let preset = presets
+        .section(BUILD)
+        .find(|p| p.name == name && !p.hidden)
+        .ok_or_else(|| {
+            let available: Vec<&str> = presets
+                .section(BUILD)
+                .filter(|p| !p.hidden)
+                .map(|p| p.name.as_str())
+                .collect();
+            anyhow!(
+                "No build preset named '{}' (available: {})",
+                name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )
+        })?;
+    let configure = presets
+        .configure_preset_of(preset, BUILD)
+        .ok_or_else(|| anyhow!("Build preset '{}' has no configurePreset", name))?;
+
+    println!("⚙️ Configuring with preset '{}'...", configure);
+    let st = cmake(config)
+        .args(["--preset", configure])
+        .status()
+        .context("Failed to invoke cmake (configure)")?;
+    if !st.success() {
+        return Err(anyhow!("cmake configure failed"));
+    }
+
+    println!("🔨 Building with preset '{}'...", name);
+    let st = cmake(config)
+        .args(["--build", "--preset", name])
+        .status()
+        .context("Failed to invoke cmake --build")?;
+    if !st.success() {
+        return Err(anyhow!("cmake build failed"));
+    }
+
+    println!("✅ Build complete.");
+    Ok(())
+}
+
+/// 產生的 preset 以 `$env{VCPKG_ROOT}` 指向 toolchain；未設定時填入 cproject 找到的 vcpkg
+fn cmake(config: &Config) -> Command {
+    let mut cmd = Command::new("cmake");
+    if std::env::var_os("VCPKG_ROOT").is_none() {
+        if let Some(root) = build::vcpkg_root(config) {
+            cmd.env("VCPKG_ROOT", root);
+        }
+    }
+    cmd
+}
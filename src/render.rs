@@ -0,0 +1,155 @@
+// src/render.rs
+
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+};
+use heck::{ToKebabCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+
+/// 建立共用的 Handlebars 渲染器，並註冊 C++ 命名慣例相關的 helper。
+/// 檔案內容、路徑、條件與鉤子指令都應該透過這裡渲染，行為才會一致。
+pub fn handlebars() -> Handlebars<'static> {
+    let mut hbs = Handlebars::new();
+    // 產生的是原始碼而非 HTML，不做 HTML 跳脫
+    hbs.register_escape_fn(handlebars::no_escape);
+    hbs.register_helper("snake_case", Box::new(snake_case));
+    hbs.register_helper("pascal_case", Box::new(pascal_case));
+    hbs.register_helper("upper_snake", Box::new(upper_snake));
+    hbs.register_helper("kebab_case", Box::new(kebab_case));
+    hbs.register_helper("cpp_identifier", Box::new(cpp_identifier_helper));
+    hbs.register_helper("include_guard", Box::new(include_guard));
+    hbs.register_helper("year", Box::new(year));
+    hbs.register_helper("uuid", Box::new(uuid));
+    hbs
+}
+
+/// 可在模板中使用的 helper：Handlebars 內建的加上 `handlebars()` 註冊的
+pub const HELPERS: &[&str] = &[
+    "if",
+    "unless",
+    "each",
+    "with",
+    "lookup",
+    "raw",
+    "log",
+    "eq",
+    "ne",
+    "gt",
+    "gte",
+    "lt",
+    "lte",
+    "and",
+    "or",
+    "not",
+    "len",
+    "snake_case",
+    "pascal_case",
+    "upper_snake",
+    "kebab_case",
+    "cpp_identifier",
+    "include_guard",
+    "year",
+    "uuid",
+];
+
+handlebars_helper!(snake_case: |s: str| s.to_snake_case());
+handlebars_helper!(pascal_case: |s: str| s.to_upper_camel_case());
+handlebars_helper!(upper_snake: |s: str| s.to_shouty_snake_case());
+handlebars_helper!(kebab_case: |s: str| s.to_kebab_case());
+handlebars_helper!(cpp_identifier_helper: |s: str| cpp_identifier(s));
+
+/// `{{include_guard name "net/Socket.h"}}` -> `MY_LIB_NET_SOCKET_H`
+fn include_guard(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let parts: Vec<String> = h
+        .params()
+        .iter()
+        .map(|p| match p.value() {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+    let mut guard: String = parts
+        .join("_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_shouty_snake_case();
+    if !(guard.ends_with("_H") || guard.ends_with("_HPP")) {
+        guard.push_str("_H");
+    }
+    out.write(&cpp_identifier(&guard))?;
+    Ok(())
+}
+
+/// `{{year}}`：helper 的優先順序高於同名的變數，因此先使用 context 中的 `year`
+/// （例如 upgrade 時 lock 記錄的年份），沒有時才使用目前的西元年份
+fn year(
+    _: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    match ctx.data().get("year") {
+        Some(serde_json::Value::String(year)) => out.write(year)?,
+        Some(year @ serde_json::Value::Number(_)) => out.write(&year.to_string())?,
+        _ => out.write(&chrono::Utc::now().format("%Y").to_string())?,
+    }
+    Ok(())
+}
+
+/// `{{uuid}}`：隨機產生的 UUID v4
+fn uuid(
+    _: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    out.write(&uuid::Uuid::new_v4().to_string())?;
+    Ok(())
+}
+
+/// 條件渲染結果的真假判斷：空字串、`false`、`0` 與 `null` 為假
+pub fn is_truthy(rendered: &str) -> bool {
+    !matches!(rendered.trim(), "" | "false" | "0" | "null")
+}
+
+/// 將任意名稱轉成合法的 C++ 識別字：
+/// 非英數字元改為 `_`，開頭為數字時補上 `_`，與關鍵字衝突時在結尾補 `_`。
+pub fn cpp_identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if CPP_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+#[rustfmt::skip]
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "asm", "auto", "bool", "break", "case", "catch", "char",
+    "class", "const", "constexpr", "continue", "default", "delete", "do", "double", "else",
+    "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if",
+    "inline", "int", "long", "module", "mutable", "namespace", "new", "noexcept", "not",
+    "nullptr", "operator", "or", "private", "protected", "public", "register", "return",
+    "short", "signed", "sizeof", "static", "struct", "switch", "template", "this", "throw",
+    "true", "try", "typedef", "typename", "union", "unsigned", "using", "virtual", "void",
+    "volatile", "while",
+];
@@ -0,0 +1,195 @@
+// src/sanitize.rs
+
+use crate::manifest::Profile;
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `--sanitize` 可用的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+    Memory,
+}
+
+impl Sanitizer {
+    /// `-fsanitize=` 使用的名稱
+    fn flag(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+            Sanitizer::Thread => "thread",
+            Sanitizer::Memory => "memory",
+        }
+    }
+
+    /// 建置目錄後綴使用的縮寫
+    fn short(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "asan",
+            Sanitizer::Undefined => "ubsan",
+            Sanitizer::Thread => "tsan",
+            Sanitizer::Memory => "msan",
+        }
+    }
+}
+
+/// 這些組合無法在同一個執行檔中使用
+const INCOMPATIBLE: &[(Sanitizer, Sanitizer)] = &[
+    (Sanitizer::Address, Sanitizer::Thread),
+    (Sanitizer::Address, Sanitizer::Memory),
+    (Sanitizer::Thread, Sanitizer::Memory),
+];
+
+/// 執行 sanitizer 建置出的程式時的預設選項；環境變數與設定檔的 `env` 優先
+const RUNTIME_OPTIONS: &[(Sanitizer, &str, &str)] = &[
+    (
+        Sanitizer::Address,
+        "ASAN_OPTIONS",
+        "detect_stack_use_after_return=1:check_initialization_order=1:strict_init_order=1",
+    ),
+    (
+        Sanitizer::Undefined,
+        "UBSAN_OPTIONS",
+        "print_stacktrace=1:halt_on_error=1",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compiler {
+    Gcc,
+    Clang,
+    Msvc,
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compiler::Gcc => "GCC",
+            Compiler::Clang => "Clang",
+            Compiler::Msvc => "MSVC",
+        })
+    }
+}
+
+/// 將 sanitizer 套用到設定檔：檢查組合、依編譯器加入編譯與連結旗標，
+/// 並改用獨立的建置目錄（例如 build/debug-asan-ubsan）
+pub fn apply(profile: &mut Profile, mut sanitizers: Vec<Sanitizer>) -> Result<()> {
+    if sanitizers.is_empty() {
+        return Ok(());
+    }
+    sanitizers.sort();
+    sanitizers.dedup();
+    for (a, b) in INCOMPATIBLE {
+        if sanitizers.contains(a) && sanitizers.contains(b) {
+            return Err(anyhow!(
+                "--sanitize {} and {} cannot be combined",
+                a.flag(),
+                b.flag()
+            ));
+        }
+    }
+
+    let compiler = detect_compiler(profile);
+    let names: Vec<&str> = sanitizers.iter().map(|s| s.flag()).collect();
+    let (compile, link) = match compiler {
+        Compiler::Msvc => {
+            if let Some(s) = sanitizers.iter().find(|s| **s != Sanitizer::Address) {
+                return Err(anyhow!(
+                    "MSVC only supports --sanitize address, not {}",
+                    s.flag()
+                ));
+            }
+            (
+                vec!["/fsanitize=address".to_string(), "/Zi".to_string()],
+                vec![],
+            )
+        }
+        Compiler::Gcc if sanitizers.contains(&Sanitizer::Memory) => {
+            return Err(anyhow!(
+                "--sanitize memory requires Clang (detected {})",
+                compiler
+            ));
+        }
+        Compiler::Gcc | Compiler::Clang => {
+            let fsanitize = format!("-fsanitize={}", names.join(","));
+            let mut compile = vec![fsanitize.clone(), "-fno-omit-frame-pointer".to_string()];
+            if sanitizers.contains(&Sanitizer::Memory) {
+                compile.push("-fsanitize-memory-track-origins".to_string());
+            }
+            (compile, vec![fsanitize])
+        }
+    };
+
+    println!("🧼 Sanitizers: {} ({})", names.join(", "), compiler);
+    for (define, flags) in [
+        ("CMAKE_C_FLAGS", &compile),
+        ("CMAKE_EXE_LINKER_FLAGS", &link),
+        ("CMAKE_SHARED_LINKER_FLAGS", &link),
+    ] {
+        if flags.is_empty() {
+            continue;
+        }
+        let value = profile.defines.entry(define.to_string()).or_default();
+        *value = std::iter::once(value.as_str())
+            .filter(|v| !v.is_empty())
+            .chain(flags.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    profile.cxx_flags.extend(compile);
+
+    let suffix: Vec<&str> = sanitizers.iter().map(|s| s.short()).collect();
+    let mut dir = profile.build_dir.clone().into_os_string();
+    dir.push(format!("-{}", suffix.join("-")));
+    profile.build_dir = PathBuf::from(dir);
+    profile.sanitizers = sanitizers;
+    Ok(())
+}
+
+/// 執行建置出的程式時要加入的 sanitizer 預設選項（環境與設定檔的 `env` 皆未設定者）。
+/// 呼叫端應先套用這些選項，再套用 `profile.env`
+pub fn runtime_env(profile: &Profile) -> Vec<(&'static str, &'static str)> {
+    RUNTIME_OPTIONS
+        .iter()
+        .filter(|(s, var, _)| {
+            profile.sanitizers.contains(s)
+                && !profile.env.contains_key(*var)
+                && env::var_os(var).is_none()
+        })
+        .map(|(_, var, options)| (*var, *options))
+        .collect()
+}
+
+/// 以 CXX（設定檔或環境）或平台預設的編譯器判斷旗標語法
+fn detect_compiler(profile: &Profile) -> Compiler {
+    let cxx = profile
+        .defines
+        .get("CMAKE_CXX_COMPILER")
+        .or(profile.env.get("CXX"))
+        .cloned()
+        .or_else(|| env::var("CXX").ok())
+        .unwrap_or_else(|| if cfg!(windows) { "cl" } else { "c++" }.to_string());
+
+    let stem = Path::new(&cxx)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if stem == "cl" || stem == "clang-cl" {
+        return Compiler::Msvc;
+    }
+    let version = Command::new(&cxx)
+        .arg("--version")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_ascii_lowercase())
+        .unwrap_or_default();
+    if stem.contains("clang") || version.contains("clang") {
+        Compiler::Clang
+    } else {
+        Compiler::Gcc
+    }
+}
@@ -0,0 +1,249 @@
+// src/upgrade.rs
+
+use crate::archetype::{self, Archetype, TemplateRoot};
+use crate::config::Config;
+use crate::embedded;
+use crate::lock::{self, ArchetypeLock};
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `cproject upgrade`：以目前的原型重新渲染，並三方合併到既有專案中
+pub fn run(config: &Config, dry_run: bool) -> Result<()> {
+    let project_dir = Path::new(".");
+    let lock = ArchetypeLock::load(project_dir)
+        .context("No archetype lock found; was this project created by cproject?")?;
+    let archetype = load_locked(config, &lock)
+        .with_context(|| format!("Failed to load archetype '{}'", lock.archetype))?;
+
+    let mut variables: serde_json::Map<String, serde_json::Value> =
+        lock.variables.clone().into_iter().collect();
+    archetype.fill_defaults(&mut variables)?;
+    let context = serde_json::Value::Object(variables.clone());
+
+    println!(
+        "⬆️ Upgrading from archetype '{}' ({})",
+        lock.archetype,
+        lock.revision.as_deref().unwrap_or("unversioned")
+    );
+
+    let mut new_lock = ArchetypeLock {
+        archetype: lock.archetype.clone(),
+        source: archetype.source(),
+        revision: archetype.revision(),
+        inside_git_repo: lock.inside_git_repo,
+        variables: variables.into_iter().collect(),
+        files: lock.files.clone(),
+    };
+
+    let mut conflicts = 0;
+    let mut changed = 0;
+    for (rel, new_content) in archetype.render_in_memory(&context, lock.inside_git_repo)? {
+        let key = lock::rel_key(&rel);
+        let target = project_dir.join(&rel);
+        let new_hash = lock::hash(&new_content);
+        let locked_hash = lock.files.get(&key);
+
+        if locked_hash == Some(&new_hash) {
+            // 原型中的這個檔案沒有變動
+            continue;
+        }
+
+        let current = fs::read(&target).ok();
+        let outcome = match (&current, locked_hash) {
+            (None, None) => {
+                write(&target, &new_content, dry_run)?;
+                "added"
+            }
+            (None, Some(_)) => "deleted locally, skipped",
+            (Some(cur), _) if *cur == new_content => "already up to date",
+            (Some(cur), Some(old)) if lock::hash(cur) == *old => {
+                write(&target, &new_content, dry_run)?;
+                "updated"
+            }
+            (Some(_), Some(_)) => {
+                let base = lock::base_path(project_dir, &key);
+                match merge3(&target, &base, &new_content, dry_run)? {
+                    Merge::Clean => "merged",
+                    Merge::Conflicts => {
+                        conflicts += 1;
+                        "CONFLICT (markers written)"
+                    }
+                    Merge::Rejected => {
+                        conflicts += 1;
+                        write_rej(&target, &new_content, dry_run)?;
+                        "CONFLICT (new version written to .rej)"
+                    }
+                }
+            }
+            (Some(_), None) => {
+                conflicts += 1;
+                write_rej(&target, &new_content, dry_run)?;
+                "CONFLICT (exists but untracked; new version written to .rej)"
+            }
+        };
+        println!("  {:<40} {}", key, outcome);
+        changed += 1;
+        if !dry_run && outcome != "deleted locally, skipped" {
+            new_lock.record_file(project_dir, &key, &new_content)?;
+        }
+    }
+
+    if dry_run {
+        println!(
+            "🧪 Dry run: {} file(s) would change; nothing was written.",
+            changed
+        );
+        return Ok(());
+    }
+    new_lock.save(project_dir)?;
+
+    if changed == 0 {
+        println!("✅ Already up to date.");
+    } else if conflicts > 0 {
+        return Err(anyhow!(
+            "Upgrade finished with {} conflict(s); resolve them and commit",
+            conflicts
+        ));
+    } else {
+        println!("✅ Upgraded {} file(s).", changed);
+    }
+    Ok(())
+}
+
+/// 載入專案建立時使用的原型目錄（lock 中的 source）。
+/// 該目錄已不存在，或是舊版 cproject 解開的內建原型時，才依名稱重新解析；
+/// 名稱目前解析到其他目錄時提出警告。
+fn load_locked(config: &Config, lock: &ArchetypeLock) -> Result<Archetype> {
+    let mut roots = archetype::template_roots(config)?;
+    let by_name = archetype::resolve_template_path(config, &roots, &lock.archetype);
+    let builtin = embedded::is_builtin_path(&lock.source);
+    if builtin || !lock.source.join("archetype.toml").is_file() {
+        if !builtin {
+            println!(
+                "⚠️ The recorded archetype source {} no longer exists; resolving '{}' by name",
+                lock.source.display(),
+                lock.archetype
+            );
+        }
+        return Archetype::load(config, &lock.archetype);
+    }
+
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    if let Some(path) = by_name.filter(|p| canonical(p) != canonical(&lock.source)) {
+        println!(
+            "⚠️ '{}' now resolves to {}, but this project was created from {}; upgrading from the latter",
+            lock.archetype,
+            path.display(),
+            lock.source.display()
+        );
+    }
+    // 父原型優先從同一個模板位置解析，與建立專案時一致
+    if let Some(root) = source_root(config, lock) {
+        roots.insert(
+            0,
+            TemplateRoot {
+                path: root,
+                source: "archetype lock".to_string(),
+            },
+        );
+    }
+    Archetype::load_path(config, &roots, &lock.archetype, lock.source.clone())
+}
+
+/// 由 source 與原型名稱推回它所在的模板位置（例如 /x/templates/my/arc -> /x/templates）
+fn source_root(config: &Config, lock: &ArchetypeLock) -> Option<PathBuf> {
+    archetype::candidate_paths(config, &lock.archetype)
+        .into_iter()
+        .find_map(|(rel, _)| {
+            let rel = Path::new(rel.trim_end_matches('/'));
+            lock.source
+                .ends_with(rel)
+                .then(|| lock.source.ancestors().nth(rel.components().count()))
+                .flatten()
+                .map(Path::to_path_buf)
+        })
+}
+
+enum Merge {
+    Clean,
+    Conflicts,
+    /// 無法進行文字合併（二進位或缺少共同祖先）
+    Rejected,
+}
+
+/// 以 `git merge-file` 將原型的新版本合併進使用者修改過的檔案
+fn merge3(target: &Path, base: &Path, new_content: &[u8], dry_run: bool) -> Result<Merge> {
+    let Ok(base_content) = fs::read(base) else {
+        return Ok(Merge::Rejected);
+    };
+    let current = fs::read(target)?;
+    if [&current[..], &base_content[..], new_content]
+        .iter()
+        .any(|c| std::str::from_utf8(c).is_err())
+    {
+        return Ok(Merge::Rejected);
+    }
+
+    let scratch = std::env::temp_dir().join(format!("cproject-upgrade-{}", std::process::id()));
+    fs::create_dir_all(&scratch)?;
+    let ours = scratch.join("ours");
+    let theirs = scratch.join("theirs");
+    fs::write(&ours, &current)?;
+    fs::write(&theirs, new_content)?;
+
+    let output = Command::new("git")
+        .args([
+            "merge-file",
+            "-p",
+            "-L",
+            "yours",
+            "-L",
+            "base",
+            "-L",
+            "archetype",
+        ])
+        .arg(&ours)
+        .arg(base)
+        .arg(&theirs)
+        .output()
+        .context("Failed to invoke git merge-file");
+    let _ = fs::remove_dir_all(&scratch);
+    let output = output?;
+
+    // 結束碼為衝突數量；負值（>127）代表錯誤
+    let code = output.status.code().unwrap_or(-1);
+    if !(0..=127).contains(&code) {
+        return Err(anyhow!(
+            "git merge-file failed for {}: {}",
+            target.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    if !dry_run {
+        fs::write(target, &output.stdout)?;
+    }
+    Ok(if code == 0 {
+        Merge::Clean
+    } else {
+        Merge::Conflicts
+    })
+}
+
+fn write(target: &Path, content: &[u8], dry_run: bool) -> Result<()> {
+    if dry_run {
+        return Ok(());
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, content)?;
+    Ok(())
+}
+
+fn write_rej(target: &Path, content: &[u8], dry_run: bool) -> Result<()> {
+    let mut rej = target.as_os_str().to_owned();
+    rej.push(".rej");
+    write(Path::new(&rej), content, dry_run)
+}
@@ -6,7 +6,12 @@ if(BUILD_TESTS)
   enable_testing()
 
   if(GTest_FOUND)
-    add_executable(run_tests tests/basic_test.cpp)
+    # Every test under tests/, compiled together with the sources in src/ (except main.cpp)
+    file(GLOB_RECURSE TEST_SOURCES CONFIGURE_DEPENDS "tests/*.cpp")
+    file(GLOB_RECURSE TESTED_SOURCES CONFIGURE_DEPENDS "src/*.cpp")
+    list(FILTER TESTED_SOURCES EXCLUDE REGEX "/src/main\\.cpp$")
+    add_executable(run_tests ${TEST_SOURCES} ${TESTED_SOURCES})
+    target_include_directories(run_tests PRIVATE ${CMAKE_SOURCE_DIR}/include)
     target_link_libraries(run_tests PRIVATE
       ${THIRD_PARTY_LIBS}
       GTest::gtest
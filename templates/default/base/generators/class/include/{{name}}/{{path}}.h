@@ -0,0 +1,11 @@
+#pragma once
+
+namespace {{namespace}}
+{
+  class {{stem}}
+  {
+  public:
+    {{stem}}();
+    ~{{stem}}();
+  };
+}
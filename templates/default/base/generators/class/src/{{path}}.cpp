@@ -0,0 +1,8 @@
+#include "{{name}}/{{path}}.h"
+
+namespace {{namespace}}
+{
+  {{stem}}::{{stem}}() = default;
+
+  {{stem}}::~{{stem}}() = default;
+}
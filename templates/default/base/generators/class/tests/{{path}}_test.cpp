@@ -0,0 +1,9 @@
+#include <gtest/gtest.h>
+
+#include "{{name}}/{{path}}.h"
+
+TEST({{pascal_case stem}}Test, Constructs)
+{
+  {{namespace}}::{{stem}} instance;
+  (void)instance;
+}
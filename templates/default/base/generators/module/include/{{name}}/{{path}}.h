@@ -0,0 +1,6 @@
+#pragma once
+
+namespace {{namespace}}::{{cpp_identifier (snake_case stem)}}
+{
+  void init();
+}
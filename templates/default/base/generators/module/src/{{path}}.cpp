@@ -0,0 +1,6 @@
+#include "{{name}}/{{path}}.h"
+
+namespace {{namespace}}::{{cpp_identifier (snake_case stem)}}
+{
+  void init() {}
+}
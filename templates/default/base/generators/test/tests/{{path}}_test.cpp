@@ -0,0 +1,6 @@
+#include <gtest/gtest.h>
+
+TEST({{pascal_case stem}}Test, Works)
+{
+  EXPECT_TRUE(true);
+}
@@ -0,0 +1,7 @@
+# Initialized by cproject. Packages will be appended here.
+set(THIRD_PARTY_LIBS)
+# You can predeclare something like:
+# find_package(spdlog CONFIG REQUIRED)
+# list(APPEND THIRD_PARTY_LIBS spdlog::spdlog)
+
+# Tests are plain C programs registered with CTest in CMakeLists.txt
@@ -0,0 +1,6 @@
+#ifndef {{include_guard name path}}
+#define {{include_guard name path}}
+
+void {{cpp_identifier (snake_case path)}}_init(void);
+
+#endif
@@ -0,0 +1,5 @@
+#include "{{name}}/{{path}}.h"
+
+void {{cpp_identifier (snake_case path)}}_init(void)
+{
+}
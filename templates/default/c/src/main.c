@@ -0,0 +1,7 @@
+#include <stdio.h>
+
+int main(void)
+{
+  printf("Hello from {{name}}!\n");
+  return 0;
+}
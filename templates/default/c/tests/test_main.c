@@ -0,0 +1,25 @@
+#include <stdio.h>
+
+static int failures = 0;
+
+static void check(int ok, const char *expr, const char *file, int line)
+{
+  if (!ok)
+  {
+    fprintf(stderr, "%s:%d: CHECK(%s) failed\n", file, line, expr);
+    failures++;
+  }
+}
+
+#define CHECK(cond) check((cond), #cond, __FILE__, __LINE__)
+
+int main(void)
+{
+  CHECK(1 + 1 == 2);
+
+  if (failures == 0)
+  {
+    printf("All tests passed\n");
+  }
+  return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,7 @@
+#pragma once
+#include <string>
+
+namespace {{cpp_identifier name}}
+{
+  inline std::string greet() { return "Hello from {{cpp_identifier name}}::greet()"; }
+}
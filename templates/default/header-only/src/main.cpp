@@ -0,0 +1,8 @@
+#include "{{name}}/{{name}}.hpp"
+#include <iostream>
+
+int main()
+{
+  std::cout << {{cpp_identifier name}}::greet() << std::endl;
+  return 0;
+}
@@ -1,3 +0,0 @@
-set(THIRD_PARTY_LIBS)
-
-# Test-related dependencies are configured in cmake/gtest.cmake
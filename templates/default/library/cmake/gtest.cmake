@@ -6,7 +6,9 @@ if(BUILD_TESTS)
   enable_testing()
 
   if(GTest_FOUND)
-    add_executable(run_tests tests/basic_test.cpp)
+    # Every test under tests/
+    file(GLOB_RECURSE TEST_SOURCES CONFIGURE_DEPENDS "tests/*.cpp")
+    add_executable(run_tests ${TEST_SOURCES})
     target_link_libraries(run_tests PRIVATE
       ${PROJECT_NAME}
       ${THIRD_PARTY_LIBS}
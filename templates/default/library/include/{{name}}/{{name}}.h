@@ -1,6 +1,6 @@
 #pragma once
 #include <string>
 
-namespace {{name}} {
+namespace {{cpp_identifier name}} {
   std::string greet();
 }
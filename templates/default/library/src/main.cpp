@@ -2,6 +2,6 @@
 #include <iostream>
 
 int main() {
-  std::cout << {{name}}::greet() << std::endl;
+  std::cout << {{cpp_identifier name}}::greet() << std::endl;
   return 0;
 }
@@ -1,7 +1,7 @@
 #include "{{name}}/{{name}}.h"
 #include <string>
 
-namespace {{name}}
+namespace {{cpp_identifier name}}
 {
-  std::string greet() { return "Hello from {{name}}::greet()"; }
+  std::string greet() { return "Hello from {{cpp_identifier name}}::greet()"; }
 }
@@ -1,12 +0,0 @@
-#include <gtest/gtest.h>
-
-TEST(SmokeTest, Multiply)
-{
-  EXPECT_EQ(2 * 2, 4);
-}
-
-int main(int argc, char **argv)
-{
-  ::testing::InitGoogleTest(&argc, argv);
-  return RUN_ALL_TESTS();
-}
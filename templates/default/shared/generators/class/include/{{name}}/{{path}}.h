@@ -0,0 +1,13 @@
+#pragma once
+
+#include "{{name}}/export.h"
+
+namespace {{namespace}}
+{
+  class {{cpp_identifier (upper_snake name)}}_EXPORT {{stem}}
+  {
+  public:
+    {{stem}}();
+    ~{{stem}}();
+  };
+}
@@ -0,0 +1,9 @@
+#pragma once
+#include <string>
+
+#include "{{name}}/export.h"
+
+namespace {{cpp_identifier name}}
+{
+  {{cpp_identifier (upper_snake name)}}_EXPORT std::string greet();
+}